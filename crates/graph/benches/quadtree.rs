@@ -0,0 +1,38 @@
+#![feature(test)]
+
+extern crate test;
+
+use graph::quadtree::{Extent, QuadtreeNode};
+use rand::{distributions::Uniform, rngs::StdRng, Rng, SeedableRng};
+use test::Bencher;
+use vector::{dim2::Vec2, Vector};
+
+const POINTS: usize = 100_000;
+
+fn points() -> Vec<(Vec2, usize)> {
+    let uniform = Uniform::new(Vec2::splat(0.), Vec2::splat(1000.));
+    StdRng::seed_from_u64(0)
+        .sample_iter(uniform)
+        .take(POINTS)
+        .zip(0..)
+        .collect()
+}
+
+#[bench]
+fn incremental_insert(b: &mut Bencher) {
+    let points = points();
+    b.iter(|| {
+        let mut tree: QuadtreeNode<usize> =
+            QuadtreeNode::new(Extent::new(Vec2::splat(0.), Vec2::splat(1000.)));
+        for &(pos, data) in &points {
+            tree.insert(pos, data).unwrap();
+        }
+        tree
+    });
+}
+
+#[bench]
+fn bulk_build(b: &mut Bencher) {
+    let points = points();
+    b.iter(|| QuadtreeNode::<usize>::build(points.iter().copied()).unwrap());
+}
@@ -1 +1,4 @@
+pub mod octree;
 pub mod quadtree;
+pub mod region_quadtree;
+pub mod tree;
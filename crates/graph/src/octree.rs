@@ -0,0 +1,229 @@
+use vector::{dim3::Vec3, geometry::Aabb3, Vector};
+
+use crate::tree::{ExtentImpls, TreeNode};
+
+pub use crate::tree::{InsertError, Iter, IterMut, NodeInfo};
+
+ExtentImpls!(
+    /// Axis aligned 3D bounding box of an octree, an [`Aabb3`] whose `min` is always lower or
+    /// equal to its `max` on all axes
+    ///
+    /// Boxes are closed: points on the border are contained.
+    Extent3,
+    Aabb3,
+    Vec3,
+    8,
+    octant_of,
+    octant,
+    octant_containing
+);
+
+impl Extent3 {
+    /// Index of the octant `pos` falls in: the quadrants of the lower z half in the order of
+    /// [`crate::quadtree::Extent::quadrant_of`], then those of the upper z half. Points on the
+    /// median planes go to the octant of lowest index, that is to the lower x, upper y and lower z
+    /// sides, like in the quadtree.
+    ///
+    /// `pos` does not need to be inside the extent.
+    pub fn octant_of(&self, pos: Vec3) -> usize {
+        let mid = self.center();
+        let right = pos.x() > mid.x();
+        let bottom = pos.y() < mid.y();
+        let back = pos.z() > mid.z();
+        4 * back as usize + 2 * bottom as usize + right as usize
+    }
+
+    /// Octant of index `index`, see [`Extent3::octant_of`] for the order
+    pub fn octant(&self, index: usize) -> Self {
+        assert!(index < 8, "octant index out of bounds: {index}");
+        let mid = self.center();
+        let (mut min, mut max) = (self.min(), self.max());
+        // Bit set: upper x, lower y, upper z
+        for (i, upper) in [true, false, true].into_iter().enumerate() {
+            if (index & (1 << i) != 0) == upper {
+                min[i] = mid[i];
+            } else {
+                max[i] = mid[i];
+            }
+        }
+        Self(Aabb3 { min, max })
+    }
+}
+
+/// Point octree, the 3D counterpart of [`crate::quadtree::QuadtreeNode`], see [`TreeNode`] for the
+/// subdivision rules
+pub type OctreeNode<T, const MAX_DATA_PER_NODE: usize = 8, const MAX_DEPTH: usize = 16> =
+    TreeNode<Extent3, Vec3, T, MAX_DATA_PER_NODE, MAX_DEPTH>;
+
+#[cfg(test)]
+mod tests {
+    use super::{Extent3, InsertError, OctreeNode};
+    use crate::quadtree::Extent;
+    use vector::{dim2::Vec2, dim3::Vec3, geometry::Aabb3, Vector};
+
+    fn v(x: f32, y: f32, z: f32) -> Vec3 {
+        Vec3::from_components(x, y, z)
+    }
+
+    fn grid() -> OctreeNode<[usize; 3], 4> {
+        let mut tree = OctreeNode::new(Extent3::new(Vec3::splat(0.), Vec3::splat(8.)));
+        for i in 0..8 {
+            for j in 0..8 {
+                for k in 0..8 {
+                    tree.insert(v(i as f32, j as f32, k as f32), [i, j, k])
+                        .unwrap();
+                }
+            }
+        }
+        tree
+    }
+
+    #[test]
+    fn extent() {
+        let extent = Extent3::new(Vec3::splat(0.), v(4., 2., 2.));
+        assert_eq!(Extent3::try_new(v(4., 2., 2.), Vec3::splat(0.)), None);
+        assert_eq!(Extent3::from_corners(v(4., 0., 2.), v(0., 2., 0.)), extent);
+        assert_eq!(
+            Extent3::from_aabb(Aabb3::new(v(4., 0., 2.), v(0., 2., 0.))),
+            Some(extent)
+        );
+        assert!(extent.contains(v(4., 1., 0.)));
+        assert!(!extent.contains(v(4., 1., -0.5)));
+        assert!(extent.intersects(&Extent3::new(v(4., 2., 2.), Vec3::splat(5.))));
+        assert!(!extent.intersects(&Extent3::new(v(1., 1., 3.), Vec3::splat(5.))));
+        assert_eq!(extent.distance_squared_to_point(v(5., 1., -1.)), 2.);
+
+        for index in 0..8 {
+            let octant = extent.octant(index);
+            assert_eq!(extent.octant_of(octant.center()), index);
+            assert_eq!(octant.size(), v(2., 1., 1.));
+        }
+        assert_eq!(extent.octant_of(extent.center()), 0);
+        assert_eq!(extent.octant(0), Extent3::new(v(0., 1., 0.), v(2., 2., 1.)));
+
+        // Same order and tie rules as the quadtree in the lower z half
+        let quad = Extent::new(Vec2::splat(0.), Vec2::from_components(4., 2.));
+        for (x, y) in [
+            (1., 1.5),
+            (3., 1.5),
+            (1., 0.5),
+            (3., 0.5),
+            (2., 1.),
+            (2., 0.5),
+        ] {
+            let index = quad.quadrant_of(Vec2::from_components(x, y));
+            assert_eq!(extent.octant_of(v(x, y, 0.5)), index);
+            assert_eq!(extent.octant_of(v(x, y, 1.5)), index + 4);
+        }
+    }
+
+    #[test]
+    fn queries() {
+        let mut tree = grid();
+        assert_eq!(tree.len(), 512);
+        assert_eq!(
+            tree.insert(v(0., 9., 0.), [0; 3]),
+            Err(InsertError {
+                key: v(0., 9., 0.),
+                data: [0; 3]
+            })
+        );
+
+        let extent = Extent3::new(v(1., 2., 3.), v(2., 2.5, 5.));
+        let mut found: Vec<_> = tree.query_rect(&extent).map(|(_, ijk)| *ijk).collect();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                [1, 2, 3],
+                [1, 2, 4],
+                [1, 2, 5],
+                [2, 2, 3],
+                [2, 2, 4],
+                [2, 2, 5]
+            ]
+        );
+
+        let center = v(4., 4., 4.);
+        assert_eq!(tree.query_radius(center, 1.).count(), 7);
+        for (_, ijk) in tree.query_radius_mut(center, 1.) {
+            *ijk = [0; 3];
+        }
+        for (_, ijk) in tree.query_rect_mut(&Extent3::new(Vec3::splat(9.), Vec3::splat(10.))) {
+            *ijk = [0; 3];
+        }
+        assert_eq!(tree.iter().filter(|(_, ijk)| *ijk == [0; 3]).count(), 8);
+
+        let pos = v(3.2, 6.9, 0.1);
+        assert_eq!(tree.nearest(pos).unwrap().1, [3, 7, 0]);
+        let nearest: Vec<_> = tree.k_nearest(pos, 3).iter().map(|(_, ijk)| *ijk).collect();
+        assert_eq!(nearest, [[3, 7, 0], [4, 7, 0], [3, 7, 1]]);
+        assert_eq!(tree.k_nearest(pos, usize::MAX).len(), 512);
+        let filtered = tree.nearest_with(pos, 2., |ijk| ijk[2] == 2);
+        assert_eq!(filtered.unwrap().1, [3, 7, 2]);
+        assert!(tree.nearest_with(pos, 1., |ijk| ijk[2] == 2).is_none());
+
+        for i in 0..8 {
+            for j in 0..8 {
+                for k in 0..8 {
+                    assert!(tree
+                        .remove(v(i as f32, j as f32, k as f32), |_| true)
+                        .is_some());
+                }
+            }
+        }
+        assert!(tree.is_empty());
+        assert!(matches!(tree, OctreeNode::Node { .. }));
+    }
+
+    #[test]
+    fn same_api_as_the_quadtree() {
+        let points: Vec<_> = grid().into_iter().collect();
+        let tree = OctreeNode::<_, 4>::build(points.iter().copied()).unwrap();
+        assert_eq!(
+            *tree.extent(),
+            Extent3::new(Vec3::splat(0.), Vec3::splat(7.))
+        );
+        assert_eq!(tree.len(), 512);
+        let mut depths = 0;
+        tree.visit(|info| {
+            depths = depths.max(info.depth);
+            true
+        });
+        assert!(depths >= 2);
+
+        let mut tree = OctreeNode::<_, 4>::from_iter_with_extent(
+            Extent3::new(Vec3::splat(0.), Vec3::splat(8.)),
+            points,
+        )
+        .unwrap();
+        for (pos, ijk) in &mut tree {
+            *ijk = [pos.x() as usize + 1, 0, 0];
+        }
+        assert!((&tree)
+            .into_iter()
+            .all(|(pos, ijk)| ijk[0] == pos.x() as usize + 1));
+
+        assert_eq!(
+            tree.update_position(v(1., 2., 3.), v(1.5, 2., 3.), |_| true),
+            Ok(true)
+        );
+        assert_eq!(tree.nearest(v(1.6, 2., 3.)).unwrap().0, v(1.5, 2., 3.));
+        tree.insert_or_grow(v(-4., 0., 20.), [0; 3]).unwrap();
+        assert!(tree.extent().contains(v(-4., 0., 20.)));
+        assert_eq!(tree.iter_mut().count(), 513);
+
+        assert_eq!(tree.drain().count(), 513);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn coincident_points() {
+        let mut tree: OctreeNode<usize, 4, 8> =
+            OctreeNode::new(Extent3::new(Vec3::splat(0.), Vec3::splat(1.)));
+        for i in 0..2000 {
+            tree.insert(Vec3::splat(0.3), i).unwrap();
+        }
+        assert_eq!(tree.query_radius(Vec3::splat(0.3), 0.).count(), 2000);
+    }
+}
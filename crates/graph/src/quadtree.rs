@@ -1,93 +1,372 @@
-use vector::{dim2::Vec2, Vector};
+use vector::{dim2::Vec2, geometry::Aabb2, Vector};
 
-#[derive(Debug, Clone, Copy)]
-pub struct Extent {
-    min: Vec2,
-    max: Vec2,
-}
+use crate::tree::{ExtentImpls, TreeNode};
+
+pub use crate::tree::{InsertError, Iter, IterMut, NodeInfo};
+
+ExtentImpls!(
+    /// Axis aligned 2D bounding box of a quadtree, an [`Aabb2`] whose `min` is always lower or
+    /// equal to its `max` on both axes
+    ///
+    /// Boxes are closed: points on the border are contained.
+    Extent,
+    Aabb2,
+    Vec2,
+    4,
+    quadrant_of,
+    quadrant,
+    quadrant_containing
+);
 
 impl Extent {
-    fn contains(&self, pos: Vec2) -> bool {
-        let [min_x, min_y] = self.min.into_array();
-        let [max_x, max_y] = self.max.into_array();
-        let [x, y] = pos.into_array();
+    /// Index of the quadrant `pos` falls in, in the order top-left, top-right, bottom-left,
+    /// bottom-right. Points on the median lines go to the left and top quadrants.
+    ///
+    /// `pos` does not need to be inside the extent.
+    pub fn quadrant_of(&self, pos: Vec2) -> usize {
+        let mid = self.center();
+        let right = pos.x() > mid.x();
+        let bottom = pos.y() < mid.y();
+        2 * bottom as usize + right as usize
+    }
 
-        min_x <= x && min_y <= y && x <= max_x && y <= max_y
+    /// Distances along the ray `origin + t * dir` at which it enters and leaves the extent,
+    /// `None` if it misses. The ray starts at `t = 0`, the entry distance is 0 when `origin` is
+    /// inside the extent.
+    pub fn intersect_ray(&self, origin: Vec2, dir: Vec2) -> Option<(f32, f32)> {
+        let (t_enter, t_exit) = self.0.intersect_ray(origin, dir)?;
+        Some((t_enter.max(0.), t_exit))
     }
-}
 
-pub enum QuadtreeNode<T, const MAX_DATA_PER_NODE: usize = 8> {
-    Node {
-        data: Vec<(Vec2, T)>,
-        extent: Extent,
-    },
-    Parent {
-        childs: Box<[QuadtreeNode<T, MAX_DATA_PER_NODE>; 4]>,
-        extent: Extent,
-    },
+    /// Quadrant of index `index`, see [`Extent::quadrant_of`] for the order
+    pub fn quadrant(&self, index: usize) -> Self {
+        let (min, mid, max) = (self.min(), self.center(), self.max());
+        let (min, max) = match index {
+            0 => (
+                Vec2::from_components(min.x(), mid.y()),
+                Vec2::from_components(mid.x(), max.y()),
+            ),
+            1 => (mid, max),
+            2 => (min, mid),
+            3 => (
+                Vec2::from_components(mid.x(), min.y()),
+                Vec2::from_components(max.x(), mid.y()),
+            ),
+            _ => panic!("quadrant index out of bounds: {index}"),
+        };
+        Self(Aabb2 { min, max })
+    }
 }
 
-impl<T, const MAX_DATA_PER_NODE: usize> QuadtreeNode<T, MAX_DATA_PER_NODE> {
-    pub fn new(extent: [Vec2; 2]) -> Self {
-        Self::Node {
-            data: Vec::with_capacity(MAX_DATA_PER_NODE),
-            extent: Extent {
-                min: extent[0],
-                max: extent[1],
-            },
+/// Point quadtree, see [`TreeNode`] for the subdivision rules
+pub type QuadtreeNode<T, const MAX_DATA_PER_NODE: usize = 8, const MAX_DEPTH: usize = 16> =
+    TreeNode<Extent, Vec2, T, MAX_DATA_PER_NODE, MAX_DEPTH>;
+
+#[cfg(test)]
+mod tests {
+    use super::{Extent, InsertError, QuadtreeNode};
+    use vector::{dim2::Vec2, geometry::Aabb2, Vector};
+
+    #[test]
+    fn extent() {
+        let extent = Extent::new(Vec2::splat(0.), Vec2::from_components(4., 2.));
+        assert_eq!(
+            Extent::try_new(Vec2::from_components(4., 2.), Vec2::splat(0.)),
+            None
+        );
+        assert_eq!(
+            Extent::from_corners(Vec2::from_components(4., 0.), Vec2::from_components(0., 2.)),
+            extent
+        );
+        assert_eq!(
+            Extent::from_aabb(Aabb2::new(Vec2::from_components(4., 2.), Vec2::splat(0.))),
+            Some(extent)
+        );
+        assert_eq!(extent.aabb().size(), extent.size());
+        assert_eq!(extent.center(), Vec2::from_components(2., 1.));
+        assert!(extent.contains(Vec2::from_components(4., 1.)));
+        assert!(!extent.contains(Vec2::from_components(4.5, 1.)));
+
+        let other = Extent::new(Vec2::from_components(4., 1.), Vec2::splat(5.));
+        assert!(extent.intersects(&other));
+        assert!(!extent.intersects(&Extent::new(Vec2::splat(5.), Vec2::splat(6.))));
+        assert_eq!(extent.union(&other).size(), Vec2::splat(5.));
+
+        for (i, pos) in [(1., 1.5), (3., 1.5), (1., 0.5), (3., 0.5)]
+            .into_iter()
+            .enumerate()
+        {
+            let pos = Vec2::from_components(pos.0, pos.1);
+            assert_eq!(extent.quadrant_of(pos), i);
+            assert!(extent.quadrant(i).contains(pos));
         }
+        assert_eq!(extent.quadrant_of(extent.center()), 0);
+
+        let small = Extent::new(
+            Vec2::from_components(2.5, 0.),
+            Vec2::from_components(3., 0.5),
+        );
+        assert!(extent.contains_extent(&small));
+        assert_eq!(extent.quadrant_containing(&small), Some(3));
+        let straddling = Extent::new(
+            Vec2::from_components(1., 0.5),
+            Vec2::from_components(3., 1.5),
+        );
+        assert_eq!(extent.quadrant_containing(&straddling), None);
+
+        let dir = Vec2::from_components(1., 0.);
+        assert_eq!(
+            extent.intersect_ray(Vec2::from_components(-2., 1.), dir),
+            Some((2., 6.))
+        );
+        assert_eq!(extent.intersect_ray(Vec2::splat(1.), dir), Some((0., 3.)));
+        assert_eq!(
+            extent.intersect_ray(Vec2::from_components(-2., 3.), dir),
+            None
+        );
+        assert_eq!(
+            extent.intersect_ray(Vec2::from_components(5., 1.), dir),
+            None
+        );
+        // Along an edge, borders are inside
+        assert_eq!(
+            extent.intersect_ray(Vec2::from_components(-2., 2.), dir),
+            Some((2., 6.))
+        );
+        assert_eq!(
+            extent.intersect_ray(
+                Vec2::from_components(0., -1.),
+                Vec2::from_components(0., 1.)
+            ),
+            Some((1., 3.))
+        );
     }
 
-    pub fn insert(&mut self, pos: Vec2, new_data: T) {
-        assert!(self.contains(pos));
-        match self {
-            QuadtreeNode::Node { data, .. } if data.len() >= MAX_DATA_PER_NODE => {
-                self.split();
-                self.insert(pos, new_data);
-            }
-            QuadtreeNode::Node { data, .. } => {
-                data.push((pos, new_data));
+    fn grid() -> QuadtreeNode<(usize, usize), 4> {
+        let mut tree = QuadtreeNode::new(Extent::new(Vec2::splat(0.), Vec2::splat(16.)));
+        for i in 0..16 {
+            for j in 0..16 {
+                tree.insert(Vec2::from_components(i as f32, j as f32), (i, j))
+                    .unwrap();
             }
+        }
+        tree
+    }
 
-            QuadtreeNode::Parent { childs, .. } => {
-                if let Some(child) = childs.iter_mut().find(|child| child.contains(pos)) {
-                    child.insert(pos, new_data)
-                }
+    #[test]
+    fn queries() {
+        let mut tree = grid();
+
+        let rect = Extent::new(
+            Vec2::from_components(2., 3.),
+            Vec2::from_components(4., 8.5),
+        );
+        let mut found: Vec<_> = tree.query_rect(&rect).map(|(_, ij)| *ij).collect();
+        found.sort();
+        let expected: Vec<_> = (2..=4).flat_map(|i| (3..=8).map(move |j| (i, j))).collect();
+        assert_eq!(found, expected);
+
+        let center = Vec2::from_components(7., 7.);
+        let mut found: Vec<_> = tree.query_radius(center, 1.).map(|(_, ij)| *ij).collect();
+        found.sort();
+        assert_eq!(found, [(6, 7), (7, 6), (7, 7), (7, 8), (8, 7)]);
+
+        for (_, ij) in tree.query_radius_mut(center, 1.5) {
+            *ij = (0, 0);
+        }
+        for (_, ij) in tree.query_rect_mut(&Extent::new(Vec2::splat(20.), Vec2::splat(30.))) {
+            *ij = (0, 0);
+        }
+        assert_eq!(tree.query_rect(tree.extent()).count(), 256);
+        assert_eq!(
+            tree.query_radius(center, 2.)
+                .filter(|(_, ij)| *ij == (0, 0))
+                .count(),
+            9
+        );
+    }
+
+    #[test]
+    fn nearest() {
+        let tree = grid();
+        let pos = Vec2::from_components(3.2, 9.9);
+        assert_eq!(tree.nearest(pos).unwrap().1, (3, 10));
+
+        let nearest: Vec<_> = tree.k_nearest(pos, 4).iter().map(|(_, ij)| *ij).collect();
+        assert_eq!(nearest, [(3, 10), (4, 10), (3, 9), (3, 11)]);
+        assert_eq!(tree.k_nearest(pos, 1000).len(), 256);
+        assert_eq!(tree.k_nearest(pos, usize::MAX).len(), 256);
+
+        let outside = Vec2::from_components(-5., 20.);
+        assert_eq!(tree.nearest(outside).unwrap().1, (0, 15));
+        assert!(tree.nearest_with(outside, 5., |_| true).is_none());
+
+        let odd = tree.nearest_with(pos, f32::INFINITY, |(i, j)| i % 2 == 1 && j % 2 == 1);
+        assert_eq!(odd.unwrap().1, (3, 9));
+        let close = tree.k_nearest_with(pos, 10, 1., |_| true);
+        assert_eq!(close.len(), 3);
+
+        let empty = QuadtreeNode::<(), 4>::new(*tree.extent());
+        assert!(empty.nearest(pos).is_none());
+    }
+
+    #[test]
+    fn remove() {
+        let mut tree = grid();
+        let pos = Vec2::from_components(5., 5.);
+        assert_eq!(tree.remove(pos, |ij| *ij == (0, 0)), None);
+        assert_eq!(tree.remove(pos, |_| true), Some((5, 5)));
+        assert_eq!(tree.remove(pos, |_| true), None);
+        assert!(tree.nearest(pos).unwrap().0 != pos);
+
+        let new = Vec2::from_components(5.5, 5.5);
+        assert_eq!(
+            tree.update_position(Vec2::from_components(6., 6.), new, |_| true),
+            Ok(true)
+        );
+        assert_eq!(tree.nearest(new).unwrap(), &(new, (6, 6)));
+        assert_eq!(tree.update_position(pos, new, |_| true), Ok(false));
+        assert!(tree
+            .update_position(new, Vec2::splat(17.), |_| true)
+            .is_err());
+        assert_eq!(tree.nearest(new).unwrap().0, new);
+
+        for i in 0..16 {
+            for j in 0..16 {
+                tree.remove(Vec2::from_components(i as f32, j as f32), |_| true);
             }
         }
+        tree.remove(new, |_| true);
+        assert!(matches!(tree, QuadtreeNode::Node { ref data, .. } if data.is_empty()));
     }
 
-    fn contains(&self, pos: Vec2) -> bool {
-        match self {
-            QuadtreeNode::Node { extent, .. } => extent.contains(pos),
-            QuadtreeNode::Parent { extent, .. } => extent.contains(pos),
+    #[test]
+    fn coincident_points() {
+        let mut tree: QuadtreeNode<usize, 4, 8> =
+            QuadtreeNode::new(Extent::new(Vec2::splat(0.), Vec2::splat(1.)));
+        let pos = Vec2::splat(0.3);
+        for i in 0..5000 {
+            tree.insert(pos, i).unwrap();
         }
+        tree.insert(Vec2::splat(0.9), 5000).unwrap();
+
+        assert_eq!(tree.query_radius(pos, 0.).count(), 5000);
+        assert_eq!(tree.k_nearest(pos, 5001).last().unwrap().1, 5000);
+        for i in 0..5000 {
+            assert_eq!(tree.remove(pos, |&data| data == i), Some(i));
+        }
+        assert!(matches!(tree, QuadtreeNode::Node { ref data, .. } if data.len() == 1));
     }
 
-    fn split(&mut self) {
-        if let QuadtreeNode::Node { data, extent } = self {
-            let mid = 0.5 * (extent.min + extent.max);
-
-            let mut out = QuadtreeNode::Parent {
-                childs: Box::new([
-                    Self::new([
-                        Vec2::from_components(extent.min.x(), mid.y()),
-                        Vec2::from_components(mid.x(), extent.max.y()),
-                    ]),
-                    Self::new([mid, extent.max]),
-                    Self::new([extent.min, mid]),
-                    Self::new([
-                        Vec2::from_components(mid.x(), extent.min.y()),
-                        Vec2::from_components(extent.max.x(), mid.y()),
-                    ]),
-                ]),
-                extent: *extent,
-            };
-
-            for (pos, data) in data.drain(..) {
-                out.insert(pos, data);
-            }
-            *self = out;
+    #[test]
+    fn insert_out_of_extent() {
+        let mut tree: QuadtreeNode<usize, 4> =
+            QuadtreeNode::new(Extent::new(Vec2::splat(0.), Vec2::splat(1.)));
+        let outside = Vec2::from_components(0.5, 1.5);
+        assert_eq!(
+            tree.insert(outside, 7),
+            Err(InsertError {
+                key: outside,
+                data: 7
+            })
+        );
+        assert!(tree.insert(Vec2::splat(f32::NAN), 7).is_err());
+
+        for i in 0..10 {
+            tree.insert(Vec2::splat(i as f32 / 10.), i).unwrap();
+        }
+        tree.insert_or_grow(outside, 10).unwrap();
+        tree.insert_or_grow(Vec2::splat(-100.), 11).unwrap();
+        assert!(tree.insert_or_grow(Vec2::splat(f32::INFINITY), 12).is_err());
+
+        assert_eq!(tree.extent().min(), Vec2::splat(-100.));
+        assert_eq!(tree.extent().max(), Vec2::from_components(1., 2.));
+        assert_eq!(tree.query_rect(tree.extent()).count(), 12);
+        assert_eq!(tree.nearest(outside).unwrap().1, 10);
+    }
+
+    #[test]
+    fn iteration() {
+        let mut tree = grid();
+        assert_eq!(tree.len(), 256);
+        assert!(!tree.is_empty());
+        assert_eq!(tree.iter().count(), 256);
+
+        for (pos, ij) in &mut tree {
+            *ij = (pos.x() as usize + 1, pos.y() as usize + 1);
+        }
+        assert!((&tree)
+            .into_iter()
+            .all(|(pos, ij)| ij.0 == pos.x() as usize + 1));
+
+        let (mut leaves, mut max_depth, mut len) = (0, 0, 0);
+        tree.visit(|info| {
+            leaves += info.is_leaf as usize;
+            max_depth = max_depth.max(info.depth);
+            len += info.len;
+            true
+        });
+        assert_eq!(len, 256);
+        assert!(leaves >= 256 / 4 && max_depth >= 3);
+        let mut visited = 0;
+        tree.visit(|info| {
+            visited += 1;
+            info.depth == 0
+        });
+        assert_eq!(visited, 5);
+
+        let extent = *tree.extent();
+        let mut drained: Vec<_> = tree.drain().map(|(_, ij)| ij).collect();
+        drained.sort();
+        assert_eq!(drained.len(), 256);
+        assert_eq!(drained[0], (1, 1));
+        assert!(tree.is_empty());
+        assert_eq!(*tree.extent(), extent);
+
+        let owned: Vec<_> = grid().into_iter().collect();
+        assert_eq!(owned.len(), 256);
+    }
+
+    #[test]
+    fn build() {
+        let points = grid().into_iter().collect::<Vec<_>>();
+        let tree = QuadtreeNode::<_, 4>::build(points.clone()).unwrap();
+        assert_eq!(
+            *tree.extent(),
+            Extent::new(Vec2::splat(0.), Vec2::splat(15.))
+        );
+        assert_eq!(tree.len(), 256);
+        assert!(tree
+            .iter()
+            .all(|(pos, ij)| *pos == Vec2::from_components(ij.0 as f32, ij.1 as f32)));
+        assert_eq!(
+            tree.nearest(Vec2::from_components(3.2, 9.9)).unwrap().1,
+            (3, 10)
+        );
+
+        // Leaves are the same as with incremental insertion
+        let leaves = |tree: &QuadtreeNode<_, 4>| {
+            let mut leaves = Vec::new();
+            tree.visit(|info| {
+                if info.is_leaf {
+                    leaves.push((info.extent.min().into_array(), info.depth, info.len));
+                }
+                true
+            });
+            leaves
+        };
+        let mut incremental = QuadtreeNode::new(*tree.extent());
+        for (pos, data) in points.iter().copied() {
+            incremental.insert(pos, data).unwrap();
         }
+        assert_eq!(leaves(&tree), leaves(&incremental));
+
+        assert!(QuadtreeNode::<(), 4>::build([]).is_none());
+        assert!(QuadtreeNode::<(), 4>::build([(Vec2::splat(f32::NAN), ())]).is_none());
+        assert!(QuadtreeNode::<_, 4>::from_iter_with_extent(
+            Extent::new(Vec2::splat(0.), Vec2::splat(10.)),
+            points
+        )
+        .is_err());
     }
 }
@@ -0,0 +1,153 @@
+use vector::dim2::Vec2;
+
+use crate::{quadtree::Extent, tree::TreeNode};
+
+/// Quadtree of items with an extent (MX-CIF quadtree), see [`TreeNode`] for the subdivision rules
+///
+/// Each item is stored in the deepest node whose extent contains it entirely, items straddling a
+/// median line stay in the parent.
+pub type RegionQuadtreeNode<T, const MAX_DATA_PER_NODE: usize = 8, const MAX_DEPTH: usize = 16> =
+    TreeNode<Extent, Extent, T, MAX_DATA_PER_NODE, MAX_DEPTH>;
+
+impl<T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize>
+    RegionQuadtreeNode<T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    /// Items whose extent contains `pos`
+    pub fn query_point(&self, pos: Vec2) -> impl Iterator<Item = &(Extent, T)> {
+        self.query_rect(&Extent::new(pos, pos))
+    }
+
+    /// First item hit by the ray `origin + t * dir` for `t >= 0`, with its distance `t`. Items
+    /// containing `origin` are hit at 0.
+    pub fn raycast(&self, origin: Vec2, dir: Vec2) -> Option<(f32, &(Extent, T))> {
+        let mut best = None;
+        if let Some((t, _)) = self.extent().intersect_ray(origin, dir) {
+            self.raycast_node(origin, dir, t, &mut best);
+        }
+        best
+    }
+
+    // `t_enter` is where the ray enters the node, a lower bound of the distances to its items
+    fn raycast_node<'a>(
+        &'a self,
+        origin: Vec2,
+        dir: Vec2,
+        t_enter: f32,
+        best: &mut Option<(f32, &'a (Extent, T))>,
+    ) {
+        if best.is_some_and(|(t, _)| t <= t_enter) {
+            return;
+        }
+        for item in self.data() {
+            if let Some((t, _)) = item.0.intersect_ray(origin, dir) {
+                if best.is_none_or(|(best_t, _)| t < best_t) {
+                    *best = Some((t, item));
+                }
+            }
+        }
+        if let TreeNode::Parent { childs, .. } = self {
+            // Nearest children first to prune the others sooner
+            let mut hits: Vec<_> = childs
+                .iter()
+                .filter_map(|child| Some((child.extent().intersect_ray(origin, dir)?.0, child)))
+                .collect();
+            hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for (t, child) in hits {
+                child.raycast_node(origin, dir, t, best);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegionQuadtreeNode;
+    use crate::quadtree::Extent;
+    use vector::dim2::Vec2;
+
+    fn rect(x0: f32, y0: f32, x1: f32, y1: f32) -> Extent {
+        Extent::new(Vec2::from_components(x0, y0), Vec2::from_components(x1, y1))
+    }
+
+    // Unit squares on a grid and a few large rectangles straddling the median lines
+    fn scene() -> RegionQuadtreeNode<usize, 4> {
+        let mut tree = RegionQuadtreeNode::new(rect(0., 0., 16., 16.));
+        for i in 0..8 {
+            for j in 0..8 {
+                let (x, y) = (2. * i as f32, 2. * j as f32);
+                tree.insert(rect(x, y, x + 1., y + 1.), 8 * i + j).unwrap();
+            }
+        }
+        tree.insert(rect(7., 0.5, 9., 1.5), 100).unwrap();
+        tree.insert(rect(0., 7.5, 16., 7.75), 101).unwrap();
+        tree
+    }
+
+    #[test]
+    fn overlap_queries() {
+        let mut tree = scene();
+        assert_eq!(tree.len(), 66);
+        assert!(tree.insert(rect(15., 15., 17., 16.), 0).is_err());
+
+        let mut found: Vec<_> = tree
+            .query_rect(&rect(5.5, 0., 8.5, 1.))
+            .map(|(_, d)| *d)
+            .collect();
+        found.sort();
+        assert_eq!(found, [24, 32, 100]);
+
+        let mut found: Vec<_> = tree
+            .query_point(Vec2::from_components(8.5, 7.6))
+            .map(|(_, d)| *d)
+            .collect();
+        found.sort();
+        assert_eq!(found, [101]);
+
+        assert_eq!(tree.remove(rect(0., 7.5, 16., 7.75), |_| true), Some(101));
+        assert_eq!(tree.query_point(Vec2::from_components(8.5, 7.6)).count(), 0);
+        for i in 0..8 {
+            for j in 0..8 {
+                let (x, y) = (2. * i as f32, 2. * j as f32);
+                let item = rect(x, y, x + 1., y + 1.);
+                assert_eq!(tree.remove(item, |_| true), Some(8 * i + j));
+            }
+        }
+        assert_eq!(tree.len(), 1);
+        assert!(matches!(tree, RegionQuadtreeNode::Node { .. }));
+    }
+
+    #[test]
+    fn raycast() {
+        let tree = scene();
+        let (t, (_, data)) = tree
+            .raycast(
+                Vec2::from_components(-5., 0.5),
+                Vec2::from_components(1., 0.),
+            )
+            .unwrap();
+        assert_eq!((t, *data), (5., 0));
+
+        let (t, (_, data)) = tree
+            .raycast(
+                Vec2::from_components(6.5, 1.25),
+                Vec2::from_components(1., 0.),
+            )
+            .unwrap();
+        assert_eq!((t, *data), (0.5, 100));
+
+        let (t, (_, data)) = tree
+            .raycast(
+                Vec2::from_components(9.5, 20.),
+                Vec2::from_components(0., -1.),
+            )
+            .unwrap();
+        assert_eq!((t, *data), (12.25, 101));
+
+        assert!(tree
+            .raycast(
+                Vec2::from_components(1.5, 0.),
+                Vec2::from_components(0., -1.)
+            )
+            .is_none());
+    }
+}
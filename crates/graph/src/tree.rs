@@ -0,0 +1,969 @@
+//! Trees subdividing an axis aligned box around its center, shared by
+//! [`crate::quadtree::QuadtreeNode`], [`crate::octree::OctreeNode`] and
+//! [`crate::region_quadtree::RegionQuadtreeNode`]
+
+use std::{cmp::Ordering, collections::BinaryHeap, fmt};
+
+/// Closed axis aligned box covered by a node, split in `CHILDS` equal boxes around its center
+pub trait Bounds: Copy + PartialEq {
+    type Point: Copy;
+
+    /// Number of children of a parent node
+    const CHILDS: usize;
+
+    fn contains(&self, pos: Self::Point) -> bool;
+
+    /// Boxes sharing only a border intersect
+    fn intersects(&self, other: &Self) -> bool;
+
+    /// 0 for points inside the box
+    fn distance_squared_to_point(&self, pos: Self::Point) -> f32;
+
+    /// Index of the child `pos` falls in, points on the median lines or planes go to the child of
+    /// lowest index. `pos` does not need to be inside the box.
+    fn child_of(&self, pos: Self::Point) -> usize;
+
+    /// Child of index `index`, see [`Bounds::child_of`] for the order
+    fn child(&self, index: usize) -> Self;
+
+    /// Smallest box containing all the points, `None` if there is none or if a coordinate is not
+    /// finite
+    fn from_finite_points(points: impl IntoIterator<Item = Self::Point>) -> Option<Self>;
+
+    /// Extends the sides facing `pos` by the size of the box, or up to `pos` if further. `None` if
+    /// `pos` is not finite.
+    fn grown_toward(&self, pos: Self::Point) -> Option<Self>;
+}
+
+/// What the data of a tree are placed by: points, or boxes in region trees
+pub trait Key<B: Bounds>: Copy + PartialEq {
+    /// Whether a tree covering `extent` can hold data with this key
+    fn is_inside(&self, extent: &B) -> bool;
+
+    /// Child of `extent` the data goes to, `None` if it has to stay in the node covering `extent`
+    fn child_in(&self, extent: &B) -> Option<usize>;
+
+    fn intersects(&self, extent: &B) -> bool;
+
+    fn distance_squared_to_point(&self, pos: B::Point) -> f32;
+}
+
+// Validated box wrapping an `Aabb` of the `vector` crate, with its `Bounds` implementation and the
+// `Key` implementations for points and boxes. `$child_of` and `$child` are the dimension specific
+// subdivision methods written by hand, `$child_containing` is generated from `$child_of`.
+macro_rules! ExtentImpls {
+    (
+        $(#[$meta: meta])*
+        $extent: ident,
+        $aabb: ident,
+        $v: ident,
+        $childs: expr,
+        $child_of: ident,
+        $child: ident,
+        $child_containing: ident
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct $extent($aabb);
+
+        impl $extent {
+            /// Panics if `min` is greater than `max` on any axis, see [`Self::try_new`]
+            pub fn new(min: $v, max: $v) -> Self {
+                Self::try_new(min, max)
+                    .expect("the min corner of an extent must not exceed its max corner")
+            }
+
+            /// `None` if `min` is greater than `max` on any axis or if a component is NaN
+            pub fn try_new(min: $v, max: $v) -> Option<Self> {
+                let (min_arr, max_arr) = (min.into_array(), max.into_array());
+                (0..min_arr.len())
+                    .all(|i| min_arr[i] <= max_arr[i])
+                    .then_some(Self($aabb { min, max }))
+            }
+
+            /// Same rules as [`Self::try_new`]
+            pub fn from_aabb(aabb: $aabb) -> Option<Self> {
+                Self::try_new(aabb.min, aabb.max)
+            }
+
+            /// Box with `a` and `b` as opposite corners, in any order
+            pub fn from_corners(a: $v, b: $v) -> Self {
+                Self($aabb::new(a, b))
+            }
+
+            /// Panics if a component of `half_size` is negative
+            pub fn from_center_half_size(center: $v, half_size: $v) -> Self {
+                Self::new(center - half_size, center + half_size)
+            }
+
+            /// Smallest extent containing all the points, `None` if there is none
+            pub fn from_points(points: impl IntoIterator<Item = $v>) -> Option<Self> {
+                $aabb::from_points(points).and_then(Self::from_aabb)
+            }
+
+            pub fn aabb(&self) -> &$aabb {
+                &self.0
+            }
+
+            pub fn min(&self) -> $v {
+                self.0.min
+            }
+
+            pub fn max(&self) -> $v {
+                self.0.max
+            }
+
+            pub fn center(&self) -> $v {
+                self.0.center()
+            }
+
+            pub fn size(&self) -> $v {
+                self.0.size()
+            }
+
+            pub fn contains(&self, pos: $v) -> bool {
+                self.0.contains_point(pos)
+            }
+
+            pub fn contains_extent(&self, other: &Self) -> bool {
+                self.contains(other.min()) && self.contains(other.max())
+            }
+
+            /// Extents sharing only a border intersect
+            pub fn intersects(&self, other: &Self) -> bool {
+                self.0.intersects(&other.0)
+            }
+
+            /// 0 for points inside the extent
+            pub fn distance_squared_to_point(&self, pos: $v) -> f32 {
+                self.0.distance_squared_to_point(pos)
+            }
+
+            /// Smallest extent containing both extents
+            pub fn union(&self, other: &Self) -> Self {
+                Self(self.0.union(&other.0))
+            }
+
+            #[doc = concat!(
+                "Index of the child containing the whole of `other`, `None` if it straddles a ",
+                "median. Follows the same rules as [`Self::", stringify!($child_of), "`]."
+            )]
+            pub fn $child_containing(&self, other: &Self) -> Option<usize> {
+                let index = self.$child_of(other.min());
+                (index == self.$child_of(other.max())).then_some(index)
+            }
+        }
+
+        impl $crate::tree::Bounds for $extent {
+            type Point = $v;
+
+            const CHILDS: usize = $childs;
+
+            fn contains(&self, pos: $v) -> bool {
+                $extent::contains(self, pos)
+            }
+
+            fn intersects(&self, other: &Self) -> bool {
+                $extent::intersects(self, other)
+            }
+
+            fn distance_squared_to_point(&self, pos: $v) -> f32 {
+                $extent::distance_squared_to_point(self, pos)
+            }
+
+            fn child_of(&self, pos: $v) -> usize {
+                self.$child_of(pos)
+            }
+
+            fn child(&self, index: usize) -> Self {
+                self.$child(index)
+            }
+
+            fn from_finite_points(points: impl IntoIterator<Item = $v>) -> Option<Self> {
+                let mut finite = true;
+                let extent = Self::from_points(points.into_iter().inspect(|pos| {
+                    finite &= pos.into_array().into_iter().all(f32::is_finite);
+                }))?;
+                finite.then_some(extent)
+            }
+
+            fn grown_toward(&self, pos: $v) -> Option<Self> {
+                let size = self.size().into_array();
+                let (mut min, mut max) = (self.min().into_array(), self.max().into_array());
+                for (i, x) in pos.into_array().into_iter().enumerate() {
+                    if !x.is_finite() {
+                        return None;
+                    }
+                    if x < min[i] {
+                        min[i] = x.min(min[i] - size[i]);
+                    } else if x > max[i] {
+                        max[i] = x.max(max[i] + size[i]);
+                    }
+                }
+                Some(Self($aabb {
+                    min: $v::from_array(min),
+                    max: $v::from_array(max),
+                }))
+            }
+        }
+
+        impl $crate::tree::Key<$extent> for $v {
+            fn is_inside(&self, extent: &$extent) -> bool {
+                extent.contains(*self)
+            }
+
+            fn child_in(&self, extent: &$extent) -> Option<usize> {
+                Some(extent.$child_of(*self))
+            }
+
+            fn intersects(&self, extent: &$extent) -> bool {
+                extent.contains(*self)
+            }
+
+            fn distance_squared_to_point(&self, pos: $v) -> f32 {
+                self.distance_squared(pos)
+            }
+        }
+
+        impl $crate::tree::Key<$extent> for $extent {
+            fn is_inside(&self, extent: &$extent) -> bool {
+                extent.contains_extent(self)
+            }
+
+            fn child_in(&self, extent: &$extent) -> Option<usize> {
+                extent.$child_containing(self)
+            }
+
+            fn intersects(&self, extent: &$extent) -> bool {
+                $extent::intersects(self, extent)
+            }
+
+            fn distance_squared_to_point(&self, pos: $v) -> f32 {
+                $extent::distance_squared_to_point(self, pos)
+            }
+        }
+    };
+}
+
+pub(crate) use ExtentImpls;
+
+/// Key rejected by the insertion in a tree, the data is handed back
+#[derive(Debug, Clone, PartialEq)]
+pub struct InsertError<K, T> {
+    pub key: K,
+    pub data: T,
+}
+
+impl<K: fmt::Debug, T> fmt::Display for InsertError<K, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not inside of the tree extent", self.key)
+    }
+}
+
+impl<K: fmt::Debug, T: fmt::Debug> std::error::Error for InsertError<K, T> {}
+
+// Area looked up by a query, used to prune the subtrees it does not reach
+trait Region<B: Bounds, K> {
+    fn intersects(&self, extent: &B) -> bool;
+    fn matches(&self, key: &K) -> bool;
+}
+
+// Data whose key intersects the box
+struct Overlapping<B>(B);
+
+impl<B: Bounds, K: Key<B>> Region<B, K> for Overlapping<B> {
+    fn intersects(&self, extent: &B) -> bool {
+        self.0.intersects(extent)
+    }
+
+    fn matches(&self, key: &K) -> bool {
+        key.intersects(&self.0)
+    }
+}
+
+struct Ball<P> {
+    center: P,
+    radius_squared: f32,
+}
+
+impl<B: Bounds, K: Key<B>> Region<B, K> for Ball<B::Point> {
+    fn intersects(&self, extent: &B) -> bool {
+        extent.distance_squared_to_point(self.center) <= self.radius_squared
+    }
+
+    fn matches(&self, key: &K) -> bool {
+        key.distance_squared_to_point(self.center) <= self.radius_squared
+    }
+}
+
+// Matches the whole tree
+struct Everywhere;
+
+impl<B: Bounds, K> Region<B, K> for Everywhere {
+    fn intersects(&self, _: &B) -> bool {
+        true
+    }
+
+    fn matches(&self, _: &K) -> bool {
+        true
+    }
+}
+
+struct Query<'a, B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize, R> {
+    region: R,
+    stack: Vec<&'a TreeNode<B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>>,
+    data: std::slice::Iter<'a, (K, T)>,
+}
+
+impl<'a, B: Bounds, K: Key<B>, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize, R>
+    Iterator for Query<'a, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH, R>
+where
+    R: Region<B, K>,
+{
+    type Item = &'a (K, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.data.find(|(key, _)| self.region.matches(key)) {
+                return Some(item);
+            }
+            let node = self.stack.pop()?;
+            if let TreeNode::Parent { childs, .. } = node {
+                self.stack.extend(
+                    childs
+                        .iter()
+                        .filter(|child| self.region.intersects(child.extent())),
+                );
+            }
+            self.data = node.data().iter();
+        }
+    }
+}
+
+struct QueryMut<'a, B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize, R> {
+    region: R,
+    stack: Vec<&'a mut TreeNode<B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>>,
+    data: std::slice::IterMut<'a, (K, T)>,
+}
+
+impl<'a, B: Bounds, K: Key<B>, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize, R>
+    Iterator for QueryMut<'a, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH, R>
+where
+    R: Region<B, K>,
+{
+    type Item = (K, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, data)) = self.data.find(|(key, _)| self.region.matches(key)) {
+                return Some((*key, data));
+            }
+            self.data = match self.stack.pop()? {
+                TreeNode::Node { data, .. } => data.iter_mut(),
+                TreeNode::Parent { data, childs, .. } => {
+                    self.stack.extend(
+                        childs
+                            .iter_mut()
+                            .filter(|child| self.region.intersects(child.extent())),
+                    );
+                    data.iter_mut()
+                }
+            };
+        }
+    }
+}
+
+/// Iterator over the data of a tree, see [`TreeNode::iter`]
+pub struct Iter<'a, B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize>(
+    Query<'a, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH, Everywhere>,
+);
+
+impl<'a, B: Bounds, K: Key<B>, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize> Iterator
+    for Iter<'a, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    type Item = &'a (K, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Iterator over the data of a tree, see [`TreeNode::iter_mut`]
+pub struct IterMut<'a, B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize>(
+    QueryMut<'a, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH, Everywhere>,
+);
+
+impl<'a, B: Bounds, K: Key<B>, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize> Iterator
+    for IterMut<'a, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    type Item = (K, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+/// Description of a node passed to the visitor of [`TreeNode::visit`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeInfo<B> {
+    pub extent: B,
+    /// 0 for the root
+    pub depth: usize,
+    /// Number of data held by the node itself, parents only hold data in region trees
+    pub len: usize,
+    pub is_leaf: bool,
+}
+
+enum Candidate<'a, B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize> {
+    Node(&'a TreeNode<B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>),
+    Data(&'a (K, T)),
+}
+
+// Entry of the best-first search, a node is keyed by the distance to its extent which is a lower
+// bound of the distances to its data
+struct Nearest<'a, B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize> {
+    distance_squared: f32,
+    candidate: Candidate<'a, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>,
+}
+
+impl<B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize> PartialEq
+    for Nearest<'_, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize> Eq
+    for Nearest<'_, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+}
+
+impl<B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize> PartialOrd
+    for Nearest<'_, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<B, K, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize> Ord
+    for Nearest<'_, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    // Reversed to turn the max-heap into a min-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance_squared.total_cmp(&self.distance_squared)
+    }
+}
+
+/// Node of a tree over the box `B` holding data placed by keys `K`, leaves are split once they
+/// hold more than `MAX_DATA_PER_NODE` data
+///
+/// Leaves at depth `MAX_DEPTH` are never split and may hold any number of data, which bounds the
+/// subdivision when many data share the same key. Data whose key fits in no child, which only
+/// happens with box keys, stay in the parent.
+pub enum TreeNode<B, K, T, const MAX_DATA_PER_NODE: usize = 8, const MAX_DEPTH: usize = 16> {
+    Node {
+        data: Vec<(K, T)>,
+        extent: B,
+    },
+    Parent {
+        /// Data straddling the medians of `extent`
+        data: Vec<(K, T)>,
+        /// `B::CHILDS` children in the order of [`Bounds::child`]
+        childs: Box<[TreeNode<B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>]>,
+        extent: B,
+    },
+}
+
+impl<B: Bounds, K: Key<B>, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize>
+    TreeNode<B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    pub fn new(extent: B) -> Self {
+        Self::Node {
+            data: Vec::with_capacity(MAX_DATA_PER_NODE),
+            extent,
+        }
+    }
+
+    /// Builds the tree in a single top-down pass, fails on the first key outside of `extent`
+    pub fn from_iter_with_extent(
+        extent: B,
+        items: impl IntoIterator<Item = (K, T)>,
+    ) -> Result<Self, InsertError<K, T>> {
+        let items = items
+            .into_iter()
+            .map(|(key, data)| {
+                if key.is_inside(&extent) {
+                    Ok((key, data))
+                } else {
+                    Err(InsertError { key, data })
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::build_node(extent, items, 0))
+    }
+
+    // Same subdivision as incremental insertion: nodes with more than `MAX_DATA_PER_NODE` data are
+    // split unless they are at `MAX_DEPTH`
+    fn build_node(extent: B, items: Vec<(K, T)>, depth: usize) -> Self {
+        if items.len() <= MAX_DATA_PER_NODE || depth >= MAX_DEPTH {
+            return Self::Node {
+                data: items,
+                extent,
+            };
+        }
+
+        // Counted first so that each child is allocated once
+        let mut lens = vec![0; B::CHILDS];
+        for (key, _) in &items {
+            if let Some(index) = key.child_in(&extent) {
+                lens[index] += 1;
+            }
+        }
+        let mut data = Vec::new();
+        let mut childs_data: Vec<_> = lens.into_iter().map(Vec::with_capacity).collect();
+        for (key, item) in items {
+            match key.child_in(&extent) {
+                Some(index) => childs_data[index].push((key, item)),
+                None => data.push((key, item)),
+            }
+        }
+
+        TreeNode::Parent {
+            data,
+            childs: childs_data
+                .into_iter()
+                .enumerate()
+                .map(|(i, data)| Self::build_node(extent.child(i), data, depth + 1))
+                .collect(),
+            extent,
+        }
+    }
+
+    /// Fails if `key` is not inside of the extent of the tree
+    pub fn insert(&mut self, key: K, new_data: T) -> Result<(), InsertError<K, T>> {
+        if !key.is_inside(self.extent()) {
+            return Err(InsertError {
+                key,
+                data: new_data,
+            });
+        }
+        self.insert_at_depth(key, new_data, 0);
+        Ok(())
+    }
+
+    fn insert_at_depth(&mut self, key: K, new_data: T, depth: usize) {
+        match self {
+            TreeNode::Node { data, .. } if data.len() >= MAX_DATA_PER_NODE && depth < MAX_DEPTH => {
+                self.split(depth);
+                self.insert_at_depth(key, new_data, depth);
+            }
+            TreeNode::Node { data, .. } => data.push((key, new_data)),
+            TreeNode::Parent {
+                data,
+                childs,
+                extent,
+            } => match key.child_in(extent) {
+                Some(index) => childs[index].insert_at_depth(key, new_data, depth + 1),
+                None => data.push((key, new_data)),
+            },
+        }
+    }
+
+    /// Removes the first data at exactly `key` accepted by `predicate`, merging the nodes left
+    /// with too few data
+    pub fn remove(&mut self, key: K, mut predicate: impl FnMut(&T) -> bool) -> Option<T> {
+        if !key.is_inside(self.extent()) {
+            return None;
+        }
+        self.remove_with(key, &mut predicate)
+    }
+
+    fn remove_with(&mut self, key: K, predicate: &mut impl FnMut(&T) -> bool) -> Option<T> {
+        let child = match self {
+            TreeNode::Node { .. } => None,
+            TreeNode::Parent { extent, .. } => key.child_in(extent),
+        };
+        let removed = match (&mut *self, child) {
+            (TreeNode::Parent { childs, .. }, Some(index)) => {
+                childs[index].remove_with(key, predicate)
+            }
+            (node, _) => {
+                let data = node.data_mut();
+                let index = data.iter().position(|(k, d)| *k == key && predicate(d))?;
+                Some(data.swap_remove(index).1)
+            }
+        };
+        if removed.is_some() {
+            self.merge();
+        }
+        removed
+    }
+
+    /// Number of data in the tree
+    pub fn len(&self) -> usize {
+        match self {
+            TreeNode::Node { data, .. } => data.len(),
+            TreeNode::Parent { data, childs, .. } => {
+                data.len() + childs.iter().map(Self::len).sum::<usize>()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Data in no particular order
+    pub fn iter(&self) -> Iter<'_, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH> {
+        Iter(self.query(Everywhere))
+    }
+
+    /// Data in no particular order, keys can only be changed by removing and inserting the data
+    /// again
+    pub fn iter_mut(&mut self) -> IterMut<'_, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH> {
+        IterMut(self.query_mut(Everywhere))
+    }
+
+    /// Removes all the data, leaving an empty leaf with the same extent
+    pub fn drain(&mut self) -> std::vec::IntoIter<(K, T)> {
+        let extent = *self.extent();
+        std::mem::replace(self, Self::new(extent))
+            .into_data()
+            .into_iter()
+    }
+
+    /// Calls `visitor` on every node in depth-first order, parents before their children.
+    /// Returning `false` from `visitor` skips the children of the node.
+    pub fn visit(&self, mut visitor: impl FnMut(&NodeInfo<B>) -> bool) {
+        self.visit_at_depth(&mut visitor, 0);
+    }
+
+    fn visit_at_depth(&self, visitor: &mut impl FnMut(&NodeInfo<B>) -> bool, depth: usize) {
+        let info = NodeInfo {
+            extent: *self.extent(),
+            depth,
+            len: self.data().len(),
+            is_leaf: matches!(self, TreeNode::Node { .. }),
+        };
+        if !visitor(&info) {
+            return;
+        }
+        if let TreeNode::Parent { childs, .. } = self {
+            for child in childs.iter() {
+                child.visit_at_depth(visitor, depth + 1);
+            }
+        }
+    }
+
+    pub fn extent(&self) -> &B {
+        match self {
+            TreeNode::Node { extent, .. } => extent,
+            TreeNode::Parent { extent, .. } => extent,
+        }
+    }
+
+    // Data held by the node itself
+    pub(crate) fn data(&self) -> &Vec<(K, T)> {
+        match self {
+            TreeNode::Node { data, .. } => data,
+            TreeNode::Parent { data, .. } => data,
+        }
+    }
+
+    fn data_mut(&mut self) -> &mut Vec<(K, T)> {
+        match self {
+            TreeNode::Node { data, .. } => data,
+            TreeNode::Parent { data, .. } => data,
+        }
+    }
+
+    /// Data whose key intersects `rect`, borders included: points inside of it, or boxes
+    /// overlapping it
+    pub fn query_rect(&self, rect: &B) -> impl Iterator<Item = &(K, T)> {
+        self.query(Overlapping(*rect))
+    }
+
+    pub fn query_rect_mut(&mut self, rect: &B) -> impl Iterator<Item = (K, &mut T)> {
+        self.query_mut(Overlapping(*rect))
+    }
+
+    /// Data whose key is at most at `radius` from `center`
+    pub fn query_radius(&self, center: B::Point, radius: f32) -> impl Iterator<Item = &(K, T)> {
+        self.query(Ball {
+            center,
+            radius_squared: radius * radius,
+        })
+    }
+
+    pub fn query_radius_mut(
+        &mut self,
+        center: B::Point,
+        radius: f32,
+    ) -> impl Iterator<Item = (K, &mut T)> {
+        self.query_mut(Ball {
+            center,
+            radius_squared: radius * radius,
+        })
+    }
+
+    /// Closest data to `pos`, `None` if the tree is empty
+    pub fn nearest(&self, pos: B::Point) -> Option<&(K, T)> {
+        self.nearest_with(pos, f32::INFINITY, |_| true)
+    }
+
+    /// Closest data to `pos` at most at `max_radius` and accepted by `filter`
+    pub fn nearest_with(
+        &self,
+        pos: B::Point,
+        max_radius: f32,
+        filter: impl FnMut(&T) -> bool,
+    ) -> Option<&(K, T)> {
+        self.k_nearest_with(pos, 1, max_radius, filter).pop()
+    }
+
+    /// The `k` closest data to `pos`, sorted by increasing distance
+    pub fn k_nearest(&self, pos: B::Point, k: usize) -> Vec<&(K, T)> {
+        self.k_nearest_with(pos, k, f32::INFINITY, |_| true)
+    }
+
+    /// The `k` closest data to `pos` at most at `max_radius` and accepted by `filter`, sorted by
+    /// increasing distance. Fewer than `k` data are returned if not enough of them qualify.
+    pub fn k_nearest_with(
+        &self,
+        pos: B::Point,
+        k: usize,
+        max_radius: f32,
+        mut filter: impl FnMut(&T) -> bool,
+    ) -> Vec<&(K, T)> {
+        let max_distance_squared = max_radius * max_radius;
+        let mut found = Vec::new();
+        let mut heap = BinaryHeap::new();
+        heap.push(Nearest {
+            distance_squared: self.extent().distance_squared_to_point(pos),
+            candidate: Candidate::Node(self),
+        });
+
+        while found.len() < k {
+            let Some(Nearest {
+                distance_squared,
+                candidate,
+            }) = heap.pop()
+            else {
+                break;
+            };
+            // Everything left in the heap is further away
+            if distance_squared > max_distance_squared {
+                break;
+            }
+            let node = match candidate {
+                Candidate::Data(item) => {
+                    found.push(item);
+                    continue;
+                }
+                Candidate::Node(node) => node,
+            };
+            heap.extend(
+                node.data()
+                    .iter()
+                    .filter(|(_, data)| filter(data))
+                    .map(|item| Nearest {
+                        distance_squared: item.0.distance_squared_to_point(pos),
+                        candidate: Candidate::Data(item),
+                    }),
+            );
+            if let TreeNode::Parent { childs, .. } = node {
+                heap.extend(childs.iter().map(|child| Nearest {
+                    distance_squared: child.extent().distance_squared_to_point(pos),
+                    candidate: Candidate::Node(child),
+                }));
+            }
+        }
+        found
+    }
+
+    fn query<R: Region<B, K>>(
+        &self,
+        region: R,
+    ) -> Query<'_, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH, R> {
+        let stack = if region.intersects(self.extent()) {
+            vec![self]
+        } else {
+            Vec::new()
+        };
+        Query {
+            region,
+            stack,
+            data: [].iter(),
+        }
+    }
+
+    fn query_mut<R: Region<B, K>>(
+        &mut self,
+        region: R,
+    ) -> QueryMut<'_, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH, R> {
+        let stack = if region.intersects(self.extent()) {
+            vec![self]
+        } else {
+            Vec::new()
+        };
+        QueryMut {
+            region,
+            stack,
+            data: [].iter_mut(),
+        }
+    }
+
+    // Turns a parent back into a leaf once it and its children, all leaves, hold less than
+    // `MAX_DATA_PER_NODE` data in total
+    fn merge(&mut self) {
+        let TreeNode::Parent {
+            data,
+            childs,
+            extent,
+        } = self
+        else {
+            return;
+        };
+        let mut len = data.len();
+        for child in childs.iter() {
+            match child {
+                TreeNode::Node { data, .. } => len += data.len(),
+                TreeNode::Parent { .. } => return,
+            }
+        }
+        if len >= MAX_DATA_PER_NODE {
+            return;
+        }
+
+        let mut merged = std::mem::take(data);
+        merged.reserve(MAX_DATA_PER_NODE - merged.len());
+        for child in childs.iter_mut() {
+            merged.append(child.data_mut());
+        }
+        *self = TreeNode::Node {
+            data: merged,
+            extent: *extent,
+        };
+    }
+
+    fn into_data(self) -> Vec<(K, T)> {
+        match self {
+            TreeNode::Node { data, .. } => data,
+            TreeNode::Parent {
+                mut data, childs, ..
+            } => {
+                for child in childs.into_vec() {
+                    data.append(&mut child.into_data());
+                }
+                data
+            }
+        }
+    }
+
+    fn split(&mut self, depth: usize) {
+        if let TreeNode::Node { data, extent } = self {
+            let mut out = TreeNode::Parent {
+                data: Vec::new(),
+                childs: (0..B::CHILDS).map(|i| Self::new(extent.child(i))).collect(),
+                extent: *extent,
+            };
+
+            for (key, data) in data.drain(..) {
+                out.insert_at_depth(key, data, depth);
+            }
+            *self = out;
+        }
+    }
+}
+
+/// Methods of the trees over points
+impl<B: Bounds, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize>
+    TreeNode<B, B::Point, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+where
+    B::Point: Key<B>,
+{
+    /// Builds the tree over the bounding extent of `points`, `None` if there is no point or if a
+    /// position is not finite
+    pub fn build(points: impl IntoIterator<Item = (B::Point, T)>) -> Option<Self> {
+        let points: Vec<_> = points.into_iter().collect();
+        let extent = B::from_finite_points(points.iter().map(|(pos, _)| *pos))?;
+        Some(Self::build_node(extent, points, 0))
+    }
+
+    /// Like [`TreeNode::insert`], but a position outside of the tree enlarges its extent toward
+    /// `pos`, at least doubling it, and rebuilds the tree. Fails only for non-finite positions.
+    pub fn insert_or_grow(
+        &mut self,
+        pos: B::Point,
+        new_data: T,
+    ) -> Result<(), InsertError<B::Point, T>> {
+        if !self.extent().contains(pos) {
+            let Some(extent) = self.extent().grown_toward(pos) else {
+                return Err(InsertError {
+                    key: pos,
+                    data: new_data,
+                });
+            };
+            let old = std::mem::replace(self, Self::new(extent));
+            for (pos, data) in old.into_data() {
+                self.insert_at_depth(pos, data, 0);
+            }
+        }
+        self.insert(pos, new_data)
+    }
+
+    /// Moves the first data at exactly `old` accepted by `predicate` to `new`, returns whether a
+    /// data was moved. Fails without modifying the tree if `new` is outside of its extent.
+    pub fn update_position(
+        &mut self,
+        old: B::Point,
+        new: B::Point,
+        predicate: impl FnMut(&T) -> bool,
+    ) -> Result<bool, InsertError<B::Point, ()>> {
+        if !self.extent().contains(new) {
+            return Err(InsertError { key: new, data: () });
+        }
+        Ok(match self.remove(old, predicate) {
+            Some(data) => {
+                self.insert_at_depth(new, data, 0);
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+impl<B: Bounds, K: Key<B>, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize> IntoIterator
+    for TreeNode<B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    type Item = (K, T);
+    type IntoIter = std::vec::IntoIter<(K, T)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_data().into_iter()
+    }
+}
+
+impl<'a, B: Bounds, K: Key<B>, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize>
+    IntoIterator for &'a TreeNode<B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    type Item = &'a (K, T);
+    type IntoIter = Iter<'a, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, B: Bounds, K: Key<B>, T, const MAX_DATA_PER_NODE: usize, const MAX_DEPTH: usize>
+    IntoIterator for &'a mut TreeNode<B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>
+{
+    type Item = (K, &'a mut T);
+    type IntoIter = IterMut<'a, B, K, T, MAX_DATA_PER_NODE, MAX_DEPTH>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
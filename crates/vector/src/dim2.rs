@@ -1,107 +1,241 @@
-use std::simd::f32x2;
+use crate::simd::{f32x2, f64x2, i32x2, mask32x2};
+
+use crate::{Matrix, Scalar, Vector};
+
+macro_rules! Dim2 {
+    ($v: ident, $m: ident, $s: ty, $simd: ident) => {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(transparent)]
+        pub struct $v(pub $simd);
+
+        // Stored in row major order for efficient SIMD multiplication
+        #[derive(Clone, Copy, Debug)]
+        #[repr(transparent)]
+        pub struct $m(pub [$simd; 2]);
+
+        impl $v {
+            #[inline]
+            pub fn from_components(x: $s, y: $s) -> Self {
+                Self::from_array([x, y])
+            }
+
+            pub fn x(self) -> $s {
+                self[0]
+            }
+            pub fn y(self) -> $s {
+                self[1]
+            }
+
+            /// Counter-clockwise perpendicular vector
+            pub fn perp(self) -> Self {
+                Self::from_components(-self.y(), self.x())
+            }
+
+            /// z component of the 3D cross product, `self.perp().dot(other)`
+            pub fn perp_dot(self, other: Self) -> $s {
+                self.x() * other.y() - self.y() * other.x()
+            }
+        }
+
+        impl crate::Vector<2> for $v {
+            type Scalar = $s;
+
+            fn add(self, other: Self) -> Self {
+                Self(self.0 + other.0)
+            }
+
+            fn sub(self, other: Self) -> Self {
+                Self(self.0 - other.0)
+            }
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+
+            fn component_mul(self, other: Self) -> Self {
+                Self(self.0 * other.0)
+            }
+
+            fn splat(f: $s) -> Self {
+                Self($simd::splat(f))
+            }
+
+            fn from_array(coords: [$s; 2]) -> Self {
+                Self($simd::from_array(coords))
+            }
+
+            fn into_array(self) -> [$s; 2] {
+                self.0.to_array()
+            }
+        }
+
+        impl $m {
+            pub fn from_array(mat: &[$s; 4]) -> Self {
+                Self::from_rows(unsafe { std::mem::transmute::<&[$s; 4], &[[$s; 2]; 2]>(mat) })
+            }
+
+            pub fn into_array(self) -> [$s; 4] {
+                unsafe { std::mem::transmute::<[$simd; 2], [$s; 4]>(self.0) }
+            }
+        }
+
+        impl crate::Matrix<2> for $m {
+            type Scalar = $s;
+            type VecDIM = $v;
+
+            fn transpose(self) -> Self {
+                let [a, b, c, d] = self.into_array();
+                Self::from_array(&[a, c, b, d])
+            }
+
+            fn from_rows(rows: &[[$s; 2]; 2]) -> Self {
+                Self(rows.map($simd::from_array))
+            }
+
+            fn identity() -> Self {
+                Self::from_rows(&[[1.0, 0.0], [0.0, 1.0]])
+            }
+
+            fn into_rows(self) -> [[$s; 2]; 2] {
+                self.0.map($simd::to_array)
+            }
+
+            fn add(self, other: Self) -> Self {
+                Self(std::array::from_fn(|i| self.0[i] + other.0[i]))
+            }
+
+            fn sub(self, other: Self) -> Self {
+                Self(std::array::from_fn(|i| self.0[i] - other.0[i]))
+            }
+
+            fn neg(self) -> Self {
+                Self(self.0.map(|a| -a))
+            }
+
+            fn vec_mul(vec: Self::VecDIM, this: Self) -> Self::VecDIM {
+                let v = vec.into_array().map($simd::splat);
+                $v(std::array::from_fn::<_, 2, _>(|i| v[i] * this.0[i])
+                    .into_iter()
+                    .sum())
+            }
+
+            fn determinant(self) -> $s {
+                let [a, b, c, d] = self.into_array();
+                a * d - b * c
+            }
+
+            fn try_inverse(self) -> Option<Self> {
+                let inv_det = <$s>::ONE / self.determinant();
+                if !inv_det.is_finite() {
+                    return None;
+                }
+                let [a, b, c, d] = self.into_array();
+                Some(Self::from_array(&[d, -b, -c, a].map(|x| x * inv_det)))
+            }
+        }
+    };
+}
 
-use crate::{Matrix, Vector};
-
-#[derive(Clone, Copy, Debug, PartialEq)]
-#[repr(transparent)]
-pub struct Vec2(pub f32x2);
-
-// Stored in row major order for efficient SIMD multiplication
-#[derive(Clone, Copy, Debug)]
-#[repr(transparent)]
-pub struct Mat2(pub [f32x2; 2]);
+Dim2!(Vec2, Mat2, f32, f32x2);
+Dim2!(DVec2, DMat2, f64, f64x2);
 
 impl Vec2 {
-    #[inline]
-    pub fn from_components(x: f32, y: f32) -> Self {
-        Self::from_array([x, y])
+    pub fn as_dvec2(self) -> DVec2 {
+        self.into()
     }
 
-    pub fn x(self) -> f32 {
-        self[0]
-    }
-    pub fn y(self) -> f32 {
-        self[1]
+    /// Rounds toward zero, saturating at the bounds of `i32`
+    pub fn as_ivec2(self) -> IVec2 {
+        IVec2::from_array(self.into_array().map(|x| x as i32))
     }
 }
 
-impl crate::Vector<2> for Vec2 {
-    fn add(self, other: Self) -> Self {
-        Self(self.0 + other.0)
+impl DVec2 {
+    /// Lossy conversion to single precision
+    pub fn as_vec2(self) -> Vec2 {
+        Vec2::from_array(self.into_array().map(|x| x as f32))
     }
+}
 
-    fn sub(self, other: Self) -> Self {
-        Self(self.0 - other.0)
+impl From<Vec2> for DVec2 {
+    fn from(v: Vec2) -> Self {
+        Self::from_array(v.into_array().map(f64::from))
     }
+}
 
-    fn neg(self) -> Self {
-        Self(-self.0)
+impl Mat2 {
+    pub fn as_dmat2(self) -> DMat2 {
+        self.into()
     }
+}
 
-    fn component_mul(self, other: Self) -> Self {
-        Self(self.0 * other.0)
+impl DMat2 {
+    /// Lossy conversion to single precision
+    pub fn as_mat2(self) -> Mat2 {
+        Mat2::from_array(&self.into_array().map(|x| x as f32))
     }
+}
 
-    fn splat(f: f32) -> Self {
-        Self(f32x2::splat(f))
+impl From<Mat2> for DMat2 {
+    fn from(m: Mat2) -> Self {
+        Self::from_array(&m.into_array().map(f64::from))
     }
+}
 
-    fn from_array(coords: [f32; 2]) -> Self {
-        Self(f32x2::from_array(coords))
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct IVec2(pub i32x2);
 
-    fn into_array(self) -> [f32; 2] {
-        unsafe { std::mem::transmute(self.0) }
-    }
-}
+// Result of component-wise comparisons
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(transparent)]
+pub struct BVec2(pub mask32x2);
 
-impl Mat2 {
-    pub fn from_array(mat: &[f32; 4]) -> Self {
-        Self::from_rows(unsafe { std::mem::transmute(mat) })
+impl IVec2 {
+    #[inline]
+    pub fn from_components(x: i32, y: i32) -> Self {
+        Self::from_array([x, y])
     }
 
-    pub fn into_array(self) -> [f32; 4] {
-        unsafe { std::mem::transmute(self.0) }
+    pub fn from_array(coords: [i32; 2]) -> Self {
+        Self(i32x2::from_array(coords))
     }
-}
 
-impl crate::Matrix<2> for Mat2 {
-    type VecDIM = Vec2;
-    fn transpose(self) -> Self {
-        let [a, b, c, d] = self.into_array();
-        Self::from_array(&[a, c, b, d])
+    pub fn into_array(self) -> [i32; 2] {
+        self.0.to_array()
     }
 
-    fn from_rows(rows: &[[f32; 2]; 2]) -> Self {
-        Mat2(rows.map(f32x2::from_array))
+    pub fn splat(i: i32) -> Self {
+        Self(i32x2::splat(i))
     }
 
-    fn identity() -> Self {
-        Self::from_rows(&[[1.0, 0.0], [0.0, 1.0]])
+    pub fn x(self) -> i32 {
+        self[0]
+    }
+    pub fn y(self) -> i32 {
+        self[1]
     }
 
-    fn into_rows(self) -> [[f32; 2]; 2] {
-        unsafe { std::mem::transmute(self.0) }
+    pub fn as_vec2(self) -> Vec2 {
+        Vec2::from_array(self.into_array().map(|x| x as f32))
     }
+}
 
-    fn add(self, other: Self) -> Self {
-        Self(std::array::from_fn(|i| self.0[i] + other.0[i]))
+impl BVec2 {
+    #[inline]
+    pub fn from_components(x: bool, y: bool) -> Self {
+        Self::from_array([x, y])
     }
 
-    fn sub(self, other: Self) -> Self {
-        Self(std::array::from_fn(|i| self.0[i] - other.0[i]))
+    pub fn from_array(coords: [bool; 2]) -> Self {
+        Self(mask32x2::from_array(coords))
     }
 
-    fn neg(self) -> Self {
-        Self(self.0.map(|a| -a))
+    pub fn into_array(self) -> [bool; 2] {
+        self.0.to_array()
     }
 
-    fn vec_mul(vec: Self::VecDIM, this: Self) -> Self::VecDIM {
-        let v = vec.into_array().map(f32x2::splat);
-        Vec2(
-            std::array::from_fn::<_, 2, _>(|i| v[i] * this.0[i])
-                .into_iter()
-                .sum(),
-        )
+    pub fn splat(b: bool) -> Self {
+        Self(mask32x2::splat(b))
     }
 }
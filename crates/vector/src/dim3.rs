@@ -0,0 +1,185 @@
+use crate::simd::{f32x4, f64x4, simd_swizzle};
+
+use crate::{Matrix, Scalar, Vector};
+
+macro_rules! Dim3 {
+    ($v: ident, $m: ident, $s: ty, $simd: ident) => {
+        // Padded to a 4 lanes vector, the last lane is always kept to 0
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(transparent)]
+        pub struct $v(pub $simd);
+
+        // Stored in row major order for efficient SIMD multiplication
+        // Each row is padded the same way as the vectors
+        #[derive(Clone, Copy, Debug)]
+        #[repr(transparent)]
+        pub struct $m(pub [$simd; 3]);
+
+        impl $v {
+            #[inline]
+            pub fn from_components(x: $s, y: $s, z: $s) -> Self {
+                Self::from_array([x, y, z])
+            }
+            pub fn x(self) -> $s {
+                self[0]
+            }
+            pub fn y(self) -> $s {
+                self[1]
+            }
+            pub fn z(self) -> $s {
+                self[2]
+            }
+
+            pub fn cross(self, other: Self) -> Self {
+                // a.yzx * b.zxy - a.zxy * b.yzx, the padding stays at 0
+                let a_yzx = simd_swizzle!(self.0, [1, 2, 0, 3]);
+                let a_zxy = simd_swizzle!(self.0, [2, 0, 1, 3]);
+                let b_yzx = simd_swizzle!(other.0, [1, 2, 0, 3]);
+                let b_zxy = simd_swizzle!(other.0, [2, 0, 1, 3]);
+                Self(a_yzx * b_zxy - a_zxy * b_yzx)
+            }
+        }
+
+        impl Vector<3> for $v {
+            type Scalar = $s;
+
+            fn from_array([x, y, z]: [$s; 3]) -> Self {
+                Self($simd::from_array([x, y, z, 0.0]))
+            }
+
+            fn into_array(self) -> [$s; 3] {
+                let [x, y, z, _] = self.0.to_array();
+                [x, y, z]
+            }
+
+            fn add(self, other: Self) -> Self {
+                Self(self.0 + other.0)
+            }
+
+            fn sub(self, other: Self) -> Self {
+                Self(self.0 - other.0)
+            }
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+
+            fn component_mul(self, other: Self) -> Self {
+                Self(self.0 * other.0)
+            }
+
+            fn splat(f: $s) -> Self {
+                Self::from_array([f; 3])
+            }
+        }
+
+        impl $m {
+            pub fn from_array(mat: &[$s; 9]) -> Self {
+                Self::from_rows(unsafe { std::mem::transmute::<&[$s; 9], &[[$s; 3]; 3]>(mat) })
+            }
+
+            pub fn into_array(self) -> [$s; 9] {
+                unsafe { std::mem::transmute::<[[$s; 3]; 3], [$s; 9]>(self.into_rows()) }
+            }
+        }
+
+        impl Matrix<3> for $m {
+            type Scalar = $s;
+            type VecDIM = $v;
+
+            fn add(self, other: Self) -> Self {
+                Self(std::array::from_fn(|i| self.0[i] + other.0[i]))
+            }
+
+            fn sub(self, other: Self) -> Self {
+                Self(std::array::from_fn(|i| self.0[i] - other.0[i]))
+            }
+
+            fn neg(self) -> Self {
+                Self(self.0.map(|a| -a))
+            }
+
+            fn transpose(self) -> Self {
+                let [[a, b, c], [d, e, f], [g, h, i]] = self.into_rows();
+                Self::from_rows(&[[a, d, g], [b, e, h], [c, f, i]])
+            }
+
+            fn from_rows(rows: &[[$s; 3]; 3]) -> Self {
+                Self(rows.map(|row| $v::from_array(row).0))
+            }
+
+            fn identity() -> Self {
+                Self::from_rows(&[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+            }
+
+            fn into_rows(self) -> [[$s; 3]; 3] {
+                self.0.map(|row| $v(row).into_array())
+            }
+
+            fn vec_mul(vec: Self::VecDIM, this: Self) -> Self::VecDIM {
+                let v = vec.into_array().map($simd::splat);
+                $v(std::array::from_fn::<_, 3, _>(|i| v[i] * this.0[i])
+                    .into_iter()
+                    .sum())
+            }
+
+            fn determinant(self) -> $s {
+                let [r0, r1, r2] = self.0.map($v);
+                r0.dot(r1.cross(r2))
+            }
+
+            fn try_inverse(self) -> Option<Self> {
+                // The columns of the adjugate are the cross products of the rows
+                let [r0, r1, r2] = self.0.map($v);
+                let c0 = r1.cross(r2);
+                let inv_det = <$s>::ONE / r0.dot(c0);
+                if !inv_det.is_finite() {
+                    return None;
+                }
+                let adj_t = [c0, r2.cross(r0), r0.cross(r1)].map(|c| c.scale(inv_det).0);
+                Some(Self(adj_t).transpose())
+            }
+        }
+    };
+}
+
+Dim3!(Vec3, Mat3, f32, f32x4);
+Dim3!(DVec3, DMat3, f64, f64x4);
+
+impl Vec3 {
+    pub fn as_dvec3(self) -> DVec3 {
+        self.into()
+    }
+}
+
+impl DVec3 {
+    /// Lossy conversion to single precision
+    pub fn as_vec3(self) -> Vec3 {
+        Vec3::from_array(self.into_array().map(|x| x as f32))
+    }
+}
+
+impl From<Vec3> for DVec3 {
+    fn from(v: Vec3) -> Self {
+        Self::from_array(v.into_array().map(f64::from))
+    }
+}
+
+impl Mat3 {
+    pub fn as_dmat3(self) -> DMat3 {
+        self.into()
+    }
+}
+
+impl DMat3 {
+    /// Lossy conversion to single precision
+    pub fn as_mat3(self) -> Mat3 {
+        Mat3::from_array(&self.into_array().map(|x| x as f32))
+    }
+}
+
+impl From<Mat3> for DMat3 {
+    fn from(m: Mat3) -> Self {
+        Self::from_array(&m.into_array().map(f64::from))
+    }
+}
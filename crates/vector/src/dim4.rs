@@ -1,119 +1,464 @@
-use std::simd::f32x4;
+use crate::simd::{f32x4, f64x4, i32x4, mask32x4, simd_swizzle, u32x4, Simd, SimdElement};
 
-use crate::{Matrix, Vector};
+use crate::{
+    dim3::{DMat3, DVec3, Mat3, Vec3},
+    Matrix, Scalar, Vector,
+};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-#[repr(transparent)]
-pub struct Vec4(pub f32x4);
+macro_rules! Dim4 {
+    ($v: ident, $m: ident, $v3: ident, $m3: ident, $s: ty, $simd: ident, $transpose: ident) => {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(transparent)]
+        pub struct $v(pub $simd);
 
-// Stored in row major order for efficient SIMD multiplication
-#[derive(Clone, Copy, Debug)]
-#[repr(transparent)]
-pub struct Mat4(pub [f32x4; 4]);
+        // Stored in row major order for efficient SIMD multiplication
+        #[derive(Clone, Copy, Debug)]
+        #[repr(transparent)]
+        pub struct $m(pub [$simd; 4]);
+
+        impl $v {
+            #[inline]
+            pub fn from_components(x: $s, y: $s, z: $s, w: $s) -> Self {
+                Self::from_array([x, y, z, w])
+            }
+            pub fn x(self) -> $s {
+                self[0]
+            }
+            pub fn y(self) -> $s {
+                self[1]
+            }
+            pub fn z(self) -> $s {
+                self[2]
+            }
+            pub fn w(self) -> $s {
+                self[3]
+            }
+        }
+
+        impl Vector<4> for $v {
+            type Scalar = $s;
+
+            fn from_array(coords: [$s; 4]) -> Self {
+                Self($simd::from_array(coords))
+            }
+
+            fn into_array(self) -> [$s; 4] {
+                self.0.to_array()
+            }
+
+            fn add(self, other: Self) -> Self {
+                Self(self.0 + other.0)
+            }
+
+            fn sub(self, other: Self) -> Self {
+                Self(self.0 - other.0)
+            }
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+
+            fn component_mul(self, other: Self) -> Self {
+                Self(self.0 * other.0)
+            }
+
+            fn splat(f: $s) -> Self {
+                Self($simd::splat(f))
+            }
+        }
+
+        impl $m {
+            pub fn from_array(mat: &[$s; 16]) -> Self {
+                Self::from_rows(unsafe { std::mem::transmute::<&[$s; 16], &[[$s; 4]; 4]>(mat) })
+            }
+
+            pub fn into_array(self) -> [$s; 16] {
+                unsafe { std::mem::transmute::<[$simd; 4], [$s; 16]>(self.0) }
+            }
+
+            /// Applies the affine part of the matrix to a point, the last row is not read
+            pub fn transform_point3(self, p: $v3) -> $v3 {
+                let [x, y, z] = p.into_array();
+                let [r0, r1, r2, _] = self.0.map($v);
+                let p = $v::from_components(x, y, z, 1.);
+                $v3::from_components(r0.dot(p), r1.dot(p), r2.dot(p))
+            }
+
+            /// Applies the linear part of the matrix to a vector, the translation is not applied
+            pub fn transform_vector3(self, v: $v3) -> $v3 {
+                let [r0, r1, r2, _] = self.0.map(|row| $v3(xyz(row)));
+                $v3::from_components(r0.dot(v), r1.dot(v), r2.dot(v))
+            }
+
+            /// Inverse of a matrix of the form
+            /// | A T |
+            /// | 0 1 |
+            /// such as the ones built by [`crate::graphic::Transform::as_mat4`].
+            ///
+            /// The last row is assumed to be `[0, 0, 0, 1]` and is not read. Returns `None` when
+            /// `A` is singular.
+            pub fn affine_inverse(self) -> Option<Self> {
+                // | A^-1 -A^-1 T |
+                // |   0      1   |
+                let [r0, r1, r2, _] = self.0;
+                let inv_a = $m3([r0, r1, r2].map(xyz)).try_inverse()?;
+                let inv_t = -(inv_a * $v3::from_components(r0[3], r1[3], r2[3]));
+
+                let [i0, i1, i2] = inv_a.0;
+                let [tx, ty, tz] = inv_t.into_array();
+                Some(Self([
+                    i0 + $simd::from_array([0., 0., 0., tx]),
+                    i1 + $simd::from_array([0., 0., 0., ty]),
+                    i2 + $simd::from_array([0., 0., 0., tz]),
+                    $simd::from_array([0., 0., 0., 1.]),
+                ]))
+            }
+        }
+
+        impl Blocks<$v3> {
+            fn new(mat: $m) -> Self {
+                let [a, b, c, d] = mat.transpose().0.map(|col| $v3(xyz(col)));
+                let last_row @ [x, y, z, w] = mat.0[3].to_array();
+                Self {
+                    a,
+                    b,
+                    c,
+                    d,
+                    last_row,
+                    s: a.cross(b),
+                    t: c.cross(d),
+                    u: y * a - x * b,
+                    v: w * c - z * d,
+                }
+            }
+        }
+
+        impl Matrix<4> for $m {
+            type Scalar = $s;
+            type VecDIM = $v;
+
+            fn add(self, other: Self) -> Self {
+                Self(std::array::from_fn(|i| self.0[i] + other.0[i]))
+            }
+
+            fn sub(self, other: Self) -> Self {
+                Self(std::array::from_fn(|i| self.0[i] - other.0[i]))
+            }
+
+            fn neg(self) -> Self {
+                Self(self.0.map(|a| -a))
+            }
+
+            fn transpose(self) -> Self {
+                Self($transpose(self.0))
+            }
+
+            fn from_rows(rows: &[[$s; 4]; 4]) -> Self {
+                Self(rows.map($simd::from_array))
+            }
+
+            fn identity() -> Self {
+                Self::from_rows(&[
+                    [1.0, 0.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                    [0.0, 0.0, 0.0, 1.0],
+                ])
+            }
+
+            fn into_rows(self) -> [[$s; 4]; 4] {
+                self.0.map($simd::to_array)
+            }
+
+            fn vec_mul(vec: Self::VecDIM, this: Self) -> Self::VecDIM {
+                let v = vec.into_array().map($simd::splat);
+                $v(std::array::from_fn::<_, 4, _>(|i| v[i] * this.0[i])
+                    .into_iter()
+                    .sum())
+            }
+
+            fn determinant(self) -> $s {
+                let Blocks { s, t, u, v, .. } = Blocks::<$v3>::new(self);
+                s.dot(v) + t.dot(u)
+            }
+
+            fn try_inverse(self) -> Option<Self> {
+                // See Lengyel, Foundations of Game Engine Development, Vol. 1, 1.7.5
+                let Blocks {
+                    a,
+                    b,
+                    c,
+                    d,
+                    last_row: [x, y, z, w],
+                    s,
+                    t,
+                    u,
+                    v,
+                } = Blocks::<$v3>::new(self);
+
+                let inv_det = <$s>::ONE / (s.dot(v) + t.dot(u));
+                if !inv_det.is_finite() {
+                    return None;
+                }
+                let [s, t, u, v] = [s, t, u, v].map(|e| e.scale(inv_det));
+
+                let row = |xyz: $v3, w: $s| xyz.0 + $simd::from_array([0., 0., 0., w]);
+                Some(Self([
+                    row(b.cross(v) + y * t, -b.dot(t)),
+                    row(v.cross(a) - x * t, a.dot(t)),
+                    row(d.cross(u) + w * s, -d.dot(s)),
+                    row(u.cross(c) - z * s, c.dot(s)),
+                ]))
+            }
+        }
+    };
+}
+
+Dim4!(Vec4, Mat4, Vec3, Mat3, f32, f32x4, transpose_rows);
+Dim4!(
+    DVec4,
+    DMat4,
+    DVec3,
+    DMat3,
+    f64,
+    f64x4,
+    transpose_rows_portable
+);
 
 impl Vec4 {
-    #[inline]
-    pub fn from_components(x: f32, y: f32, z: f32, w: f32) -> Self {
-        Self::from_array([x, y, z, w])
+    pub fn as_dvec4(self) -> DVec4 {
+        self.into()
     }
-    pub fn x(self) -> f32 {
-        self[0]
+
+    /// Rounds toward zero, saturating at the bounds of `i32`
+    pub fn as_ivec4(self) -> IVec4 {
+        IVec4::from_array(self.into_array().map(|x| x as i32))
     }
-    pub fn y(self) -> f32 {
-        self[1]
+
+    /// Rounds toward zero, saturating at the bounds of `u32`
+    pub fn as_uvec4(self) -> UVec4 {
+        UVec4::from_array(self.into_array().map(|x| x as u32))
     }
-    pub fn z(self) -> f32 {
-        self[2]
+}
+
+impl DVec4 {
+    /// Lossy conversion to single precision
+    pub fn as_vec4(self) -> Vec4 {
+        Vec4::from_array(self.into_array().map(|x| x as f32))
     }
-    pub fn w(self) -> f32 {
-        self[3]
+}
+
+impl From<Vec4> for DVec4 {
+    fn from(v: Vec4) -> Self {
+        Self::from_array(v.into_array().map(f64::from))
     }
 }
 
-impl Vector<4> for Vec4 {
-    fn from_array(coords: [f32; 4]) -> Self {
-        Self(f32x4::from_array(coords))
+impl Mat4 {
+    pub fn as_dmat4(self) -> DMat4 {
+        self.into()
     }
+}
 
-    fn into_array(self) -> [f32; 4] {
-        unsafe { std::mem::transmute(self.0) }
+impl DMat4 {
+    /// Lossy conversion to single precision
+    pub fn as_mat4(self) -> Mat4 {
+        Mat4::from_array(&self.into_array().map(|x| x as f32))
     }
+}
 
-    fn add(self, other: Self) -> Self {
-        Self(self.0 + other.0)
+impl From<Mat4> for DMat4 {
+    fn from(m: Mat4) -> Self {
+        Self::from_array(&m.into_array().map(f64::from))
     }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct IVec4(pub i32x4);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct UVec4(pub u32x4);
+
+// Result of component-wise comparisons
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(transparent)]
+pub struct BVec4(pub mask32x4);
+
+macro_rules! IntDim4 {
+    ($v: ident, $s: ty, $simd: ident) => {
+        impl $v {
+            #[inline]
+            pub fn from_components(x: $s, y: $s, z: $s, w: $s) -> Self {
+                Self::from_array([x, y, z, w])
+            }
+
+            pub fn from_array(coords: [$s; 4]) -> Self {
+                Self($simd::from_array(coords))
+            }
+
+            pub fn into_array(self) -> [$s; 4] {
+                self.0.to_array()
+            }
+
+            pub fn splat(i: $s) -> Self {
+                Self($simd::splat(i))
+            }
+
+            pub fn x(self) -> $s {
+                self[0]
+            }
+            pub fn y(self) -> $s {
+                self[1]
+            }
+            pub fn z(self) -> $s {
+                self[2]
+            }
+            pub fn w(self) -> $s {
+                self[3]
+            }
+
+            pub fn as_vec4(self) -> Vec4 {
+                Vec4::from_array(self.into_array().map(|x| x as f32))
+            }
+        }
+    };
+}
+
+IntDim4!(IVec4, i32, i32x4);
+IntDim4!(UVec4, u32, u32x4);
 
-    fn sub(self, other: Self) -> Self {
-        Self(self.0 - other.0)
+impl IVec4 {
+    /// Negative components wrap around
+    pub fn as_uvec4(self) -> UVec4 {
+        UVec4::from_array(self.into_array().map(|x| x as u32))
     }
+}
 
-    fn neg(self) -> Self {
-        Self(-self.0)
+impl UVec4 {
+    /// Components above `i32::MAX` wrap around
+    pub fn as_ivec4(self) -> IVec4 {
+        IVec4::from_array(self.into_array().map(|x| x as i32))
     }
+}
 
-    fn component_mul(self, other: Self) -> Self {
-        Self(self.0 * other.0)
+impl BVec4 {
+    #[inline]
+    pub fn from_components(x: bool, y: bool, z: bool, w: bool) -> Self {
+        Self::from_array([x, y, z, w])
     }
 
-    fn splat(f: f32) -> Self {
-        Self(f32x4::splat(f))
+    pub fn from_array(coords: [bool; 4]) -> Self {
+        Self(mask32x4::from_array(coords))
     }
-}
 
-impl Mat4 {
-    pub fn from_array(mat: &[f32; 16]) -> Self {
-        Self::from_rows(unsafe { std::mem::transmute(mat) })
+    pub fn into_array(self) -> [bool; 4] {
+        self.0.to_array()
     }
 
-    pub fn into_array(self) -> [f32; 16] {
-        unsafe { std::mem::transmute(self.0) }
+    pub fn splat(b: bool) -> Self {
+        Self(mask32x4::splat(b))
     }
 }
 
-impl Matrix<4> for Mat4 {
-    type VecDIM = Vec4;
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn transpose_rows(rows: [f32x4; 4]) -> [f32x4; 4] {
+    transpose_rows_sse(rows)
+}
 
-    fn add(self, other: Self) -> Self {
-        Self(std::array::from_fn(|i| self.0[i] + other.0[i]))
-    }
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+fn transpose_rows(rows: [f32x4; 4]) -> [f32x4; 4] {
+    transpose_rows_portable(rows)
+}
 
-    fn sub(self, other: Self) -> Self {
-        Self(std::array::from_fn(|i| self.0[i] - other.0[i]))
-    }
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn transpose_rows_sse(rows: [f32x4; 4]) -> [f32x4; 4] {
+    let [mut a, mut b, mut c, mut d] = rows.map(core::arch::x86_64::__m128::from);
+    unsafe { core::arch::x86_64::_MM_TRANSPOSE4_PS(&mut a, &mut b, &mut c, &mut d) };
+    [a, b, c, d].map(f32x4::from)
+}
+
+fn transpose_rows_portable<T: SimdElement>([a, b, c, d]: [Simd<T, 4>; 4]) -> [Simd<T, 4>; 4] {
+    // Same shuffles as _MM_TRANSPOSE4_PS: interleave pairs of rows then merge the halves
+    let ab_lo = simd_swizzle!(a, b, [0, 4, 1, 5]);
+    let ab_hi = simd_swizzle!(a, b, [2, 6, 3, 7]);
+    let cd_lo = simd_swizzle!(c, d, [0, 4, 1, 5]);
+    let cd_hi = simd_swizzle!(c, d, [2, 6, 3, 7]);
+    [
+        simd_swizzle!(ab_lo, cd_lo, [0, 1, 4, 5]),
+        simd_swizzle!(ab_lo, cd_lo, [2, 3, 6, 7]),
+        simd_swizzle!(ab_hi, cd_hi, [0, 1, 4, 5]),
+        simd_swizzle!(ab_hi, cd_hi, [2, 3, 6, 7]),
+    ]
+}
+
+/// Drops the last lane
+fn xyz<T: SimdElement + Scalar>(v: Simd<T, 4>) -> Simd<T, 4> {
+    simd_swizzle!(v, Simd::splat(T::ZERO), [0, 1, 2, 4])
+}
 
-    fn neg(self) -> Self {
-        Self(self.0.map(|a| -a))
+/// Decomposition of a matrix used by the determinant and the inverse:
+/// a, b, c, d are the first three components of the columns
+struct Blocks<V: Vector<3>> {
+    a: V,
+    b: V,
+    c: V,
+    d: V,
+    last_row: [V::Scalar; 4],
+    s: V,
+    t: V,
+    u: V,
+    v: V,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simd::f32x4;
+
+    use super::{DMat4, DVec4, Mat4, Vec4};
+    use crate::{Matrix, Vector};
+
+    fn reference(rows: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+        std::array::from_fn(|i| std::array::from_fn(|j| rows[j][i]))
     }
 
-    fn transpose(self) -> Self {
-        let [mut a, mut b, mut c, mut d] = self.0.map(core::arch::x86_64::__m128::from);
-        unsafe { core::arch::x86_64::_MM_TRANSPOSE4_PS(&mut a, &mut b, &mut c, &mut d) };
-        Self([a, b, c, d].map(f32x4::from))
+    fn sample() -> [[f32; 4]; 4] {
+        std::array::from_fn(|i| std::array::from_fn(|j| (4 * i + j) as f32))
     }
 
-    fn from_rows(rows: &[[f32; 4]; 4]) -> Self {
-        Mat4(rows.map(f32x4::from_array))
+    #[test]
+    fn transpose() {
+        let rows = sample();
+        assert_eq!(
+            Mat4::from_rows(&rows).transpose().into_rows(),
+            reference(rows)
+        );
     }
 
-    fn identity() -> Self {
-        Self::from_rows(&[
-            [1.0, 0.0, 0.0, 0.0],
-            [0.0, 1.0, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0],
-            [0.0, 0.0, 0.0, 1.0],
-        ])
+    #[test]
+    fn transpose_portable() {
+        let rows = sample();
+        let out = super::transpose_rows_portable(rows.map(f32x4::from_array));
+        assert_eq!(out.map(f32x4::to_array), reference(rows));
     }
 
-    fn into_rows(self) -> [[f32; 4]; 4] {
-        unsafe { std::mem::transmute(self.0) }
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[test]
+    fn transpose_sse() {
+        let rows = sample();
+        let out = super::transpose_rows_sse(rows.map(f32x4::from_array));
+        assert_eq!(out.map(f32x4::to_array), reference(rows));
     }
 
-    fn vec_mul(vec: Self::VecDIM, this: Self) -> Self::VecDIM {
-        let v = vec.into_array().map(f32x4::splat);
-        Vec4(
-            std::array::from_fn::<_, 4, _>(|i| v[i] * this.0[i])
-                .into_iter()
-                .sum(),
-        )
+    #[test]
+    fn precision_conversions() {
+        let v = Vec4::from_components(1., 2., 3., 4.);
+        assert_eq!(v.as_dvec4(), DVec4::from_components(1., 2., 3., 4.));
+        assert_eq!(v.as_dvec4().as_vec4(), v);
+
+        let m = DMat4::from_rows(&sample().map(|row| row.map(f64::from)));
+        assert_eq!(m.transpose().as_mat4().into_rows(), reference(sample()));
+        assert_eq!(DVec4::splat(0.1).as_vec4(), Vec4::splat(0.1));
     }
 }
@@ -0,0 +1,105 @@
+use crate::{
+    dim3::Vec3,
+    dim4::{Mat4, Vec4},
+    graphic::DepthRange,
+    Vector,
+};
+
+/// Six planes bounding the volume seen by a camera
+///
+/// Each plane is stored as `[a, b, c, d]` with a unit normal pointing inside the frustum: a point
+/// `p` is on the inner side when `a * p.x + b * p.y + c * p.z + d >= 0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a view-projection matrix (Gribb & Hartmann), `depth_range` must be
+    /// the one the projection was built with.
+    pub fn from_mat4(view_proj: &Mat4, depth_range: DepthRange) -> Self {
+        let [r0, r1, r2, r3] = view_proj.0.map(Vec4);
+        let (near, far) = match depth_range {
+            DepthRange::NegativeOneToOne => (r3 + r2, r3 - r2),
+            DepthRange::ZeroToOne => (r2, r3 - r2),
+            DepthRange::ReverseZ => (r3 - r2, r2),
+        };
+        Self {
+            planes: [r3 + r0, r3 - r0, r3 + r1, r3 - r1, near, far].map(normalize_plane),
+        }
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        let p = Vec4::from_components(p.x(), p.y(), p.z(), 1.);
+        self.planes.iter().all(|plane| plane.dot(p) >= 0.)
+    }
+
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        let c = Vec4::from_components(center.x(), center.y(), center.z(), 1.);
+        self.planes.iter().all(|plane| plane.dot(c) >= -radius)
+    }
+
+    /// Conservative test: a box crossing the extension of two planes outside the frustum may
+    /// still be reported as intersecting
+    pub fn intersects_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        let min = Vec4::from_components(min.x(), min.y(), min.z(), 1.);
+        let max = Vec4::from_components(max.x(), max.y(), max.z(), 1.);
+        self.planes.iter().all(|&plane| {
+            // Corner furthest along the normal
+            let corner = Vec4::select(plane.cmpge(Vec4::splat(0.)), max, min);
+            plane.dot(corner) >= 0.
+        })
+    }
+}
+
+// Planes with a null normal, like the far plane of an infinite projection, never cull anything
+fn normalize_plane(plane: Vec4) -> Vec4 {
+    let length = Vec3::from_components(plane.x(), plane.y(), plane.z()).length();
+    if length > 0. {
+        plane.scale(1. / length)
+    } else {
+        Vec4::from_components(0., 0., 0., 1.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Frustum;
+    use crate::{
+        dim3::Vec3,
+        graphic::{DepthRange, PerspCamera},
+        Vector,
+    };
+
+    #[test]
+    fn culling() {
+        for depth_range in [
+            DepthRange::NegativeOneToOne,
+            DepthRange::ZeroToOne,
+            DepthRange::ReverseZ,
+        ] {
+            let projection = PerspCamera::new(1., std::f32::consts::FRAC_PI_2, 10., 1.)
+                .with_depth_range(depth_range);
+            let frustum = Frustum::from_mat4(&projection.as_mat4(), depth_range);
+
+            assert!(frustum.contains_point(Vec3::from_components(0., 0., -5.)));
+            assert!(frustum.contains_point(Vec3::from_components(4.9, 0., -5.)));
+            assert!(!frustum.contains_point(Vec3::from_components(5.1, 0., -5.)));
+            assert!(!frustum.contains_point(Vec3::from_components(0., 0., -0.5)));
+            assert!(!frustum.contains_point(Vec3::from_components(0., 0., -11.)));
+
+            assert!(frustum.intersects_sphere(Vec3::from_components(0., 0., -11.), 2.));
+            assert!(!frustum.intersects_sphere(Vec3::from_components(0., 0., 1.), 1.5));
+
+            assert!(frustum.intersects_aabb(Vec3::splat(-1.), Vec3::from_components(1., 1., 4.)));
+            assert!(!frustum.intersects_aabb(
+                Vec3::from_components(7., -1., -6.),
+                Vec3::from_components(8., 1., -5.)
+            ));
+        }
+
+        let infinite = PerspCamera::infinite(1., std::f32::consts::FRAC_PI_2, 1.);
+        let frustum = Frustum::from_mat4(&infinite.as_mat4(), DepthRange::ZeroToOne);
+        assert!(frustum.contains_point(Vec3::from_components(0., 0., -1e6)));
+    }
+}
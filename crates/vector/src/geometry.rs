@@ -0,0 +1,495 @@
+use crate::{
+    dim2::Vec2,
+    dim3::{DMat3, Vec3},
+    dim4::Mat4,
+    graphic::Transform,
+    Matrix, Vector,
+};
+
+/// Shapes that can be moved by an affine matrix
+pub trait Transformable: Sized {
+    /// The last row of `mat` is assumed to be `[0, 0, 0, 1]`
+    fn transform_mat4(&self, mat: &Mat4) -> Self;
+
+    fn transform(&self, transform: &Transform) -> Self {
+        self.transform_mat4(&transform.as_mat4())
+    }
+}
+
+/// Half-line starting at `origin`, `dir` is kept normalized
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub dir: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, dir: Vec3) -> Self {
+        Self {
+            origin,
+            dir: dir.normalize(),
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + t * self.dir
+    }
+}
+
+impl Transformable for Ray {
+    fn transform_mat4(&self, mat: &Mat4) -> Self {
+        Self::new(
+            mat.transform_point3(self.origin),
+            mat.transform_vector3(self.dir),
+        )
+    }
+}
+
+/// Points `p` such that `normal.dot(p) + d == 0`, `normal` is kept normalized
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub d: f32,
+}
+
+impl Plane {
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Self {
+        let normal = normal.normalize();
+        Self {
+            normal,
+            d: -normal.dot(point),
+        }
+    }
+
+    /// The normal follows the right-hand rule, `None` if the points are colinear
+    pub fn from_points(a: Vec3, b: Vec3, c: Vec3) -> Option<Self> {
+        let normal = (b - a).cross(c - a).try_normalize()?;
+        Some(Self::from_point_normal(a, normal))
+    }
+
+    /// Positive on the side the normal points to
+    pub fn signed_distance(&self, p: Vec3) -> f32 {
+        self.normal.dot(p) + self.d
+    }
+
+    pub fn project_point(&self, p: Vec3) -> Vec3 {
+        p - self.signed_distance(p) * self.normal
+    }
+
+    /// Same plane with the sides swapped
+    pub fn flip(&self) -> Self {
+        Self {
+            normal: -self.normal,
+            d: -self.d,
+        }
+    }
+}
+
+impl Transformable for Plane {
+    fn transform_mat4(&self, mat: &Mat4) -> Self {
+        // Normals are transformed by the cofactor matrix det(A) A^-T, whose columns are the
+        // cross products of the columns of A. Unlike the inverse it exists for singular matrices.
+        let [c0, c1, c2, _] = mat.transpose().0.map(|col| {
+            let [x, y, z, _] = col.to_array();
+            Vec3::from_components(x, y, z)
+        });
+        let [nx, ny, nz] = self.normal.into_array();
+        let cofactor_normal = nx * c1.cross(c2) + ny * c2.cross(c0) + nz * c0.cross(c1);
+        let det_sign = c0.dot(c1.cross(c2)).signum();
+        let point = mat.transform_point3(-self.d * self.normal);
+        Self::from_point_normal(point, det_sign * cofactor_normal)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        self.center.distance_squared(p) <= self.radius * self.radius
+    }
+
+    /// Smallest sphere containing both spheres
+    pub fn merge(&self, other: &Self) -> Self {
+        let dist = self.center.distance(other.center);
+        if dist + other.radius <= self.radius {
+            return *self;
+        }
+        if dist + self.radius <= other.radius {
+            return *other;
+        }
+        let radius = (dist + self.radius + other.radius) / 2.;
+        let dir = (other.center - self.center).scale(1. / dist);
+        Self {
+            center: self.center + (radius - self.radius) * dir,
+            radius,
+        }
+    }
+
+    /// Smallest sphere containing `self` and `p`
+    pub fn expand(&self, p: Vec3) -> Self {
+        self.merge(&Self::new(p, 0.))
+    }
+
+    pub fn aabb(&self) -> Aabb3 {
+        let r = Vec3::splat(self.radius);
+        Aabb3 {
+            min: self.center - r,
+            max: self.center + r,
+        }
+    }
+}
+
+impl Transformable for Sphere {
+    /// The radius is scaled by the largest stretch of the linear part, its largest singular value,
+    /// so the result stays a bounding sphere under any non-uniform scaling
+    fn transform_mat4(&self, mat: &Mat4) -> Self {
+        let [r0, r1, r2, _] = mat.into_rows();
+        let linear = DMat3::from_rows(&[r0, r1, r2].map(|[x, y, z, _]| [x, y, z].map(f64::from)));
+        Self {
+            center: mat.transform_point3(self.center),
+            radius: self.radius * largest_singular_value(linear) as f32,
+        }
+    }
+}
+
+// Square root of the largest eigenvalue of the symmetric `m^T m`, in closed form, see Smith,
+// "Eigenvalues of a symmetric 3x3 matrix", 1961
+fn largest_singular_value(m: DMat3) -> f64 {
+    let g = (m.transpose() * m).into_rows();
+    let q = (g[0][0] + g[1][1] + g[2][2]) / 3.;
+    let off_diagonal = g[0][1] * g[0][1] + g[0][2] * g[0][2] + g[1][2] * g[1][2];
+    let diagonal: f64 = (0..3).map(|i| (g[i][i] - q) * (g[i][i] - q)).sum();
+    let p = ((diagonal + 2. * off_diagonal) / 6.).sqrt();
+    if p == 0. {
+        // Multiple of the identity: rotation with an uniform scaling
+        return q.sqrt();
+    }
+    let b: [[f64; 3]; 3] =
+        std::array::from_fn(|i| std::array::from_fn(|j| (g[i][j] - q * (i == j) as u8 as f64) / p));
+    let phi = (DMat3::from_rows(&b).determinant() / 2.)
+        .clamp(-1., 1.)
+        .acos()
+        / 3.;
+    (q + 2. * p * phi.cos()).max(0.).sqrt()
+}
+
+macro_rules! AabbImpls {
+    ($aabb: ident, $v: ident) => {
+        /// Axis aligned bounding box
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct $aabb {
+            pub min: $v,
+            pub max: $v,
+        }
+
+        impl $aabb {
+            /// Box with `a` and `b` as opposite corners, in any order
+            pub fn new(a: $v, b: $v) -> Self {
+                Self {
+                    min: a.min(b),
+                    max: a.max(b),
+                }
+            }
+
+            pub fn from_point(p: $v) -> Self {
+                Self { min: p, max: p }
+            }
+
+            /// `None` if `points` is empty
+            pub fn from_points(points: impl IntoIterator<Item = $v>) -> Option<Self> {
+                let mut points = points.into_iter();
+                let first = Self::from_point(points.next()?);
+                Some(points.fold(first, |aabb, p| aabb.expand(p)))
+            }
+
+            pub fn center(&self) -> $v {
+                0.5 * (self.min + self.max)
+            }
+
+            pub fn size(&self) -> $v {
+                self.max - self.min
+            }
+
+            pub fn contains_point(&self, p: $v) -> bool {
+                let (min, max) = (self.min.into_array(), self.max.into_array());
+                p.into_array()
+                    .iter()
+                    .enumerate()
+                    .all(|(i, &x)| min[i] <= x && x <= max[i])
+            }
+
+            /// Smallest box containing both boxes
+            pub fn union(&self, other: &Self) -> Self {
+                Self {
+                    min: self.min.min(other.min),
+                    max: self.max.max(other.max),
+                }
+            }
+
+            /// Smallest box containing `self` and `p`
+            pub fn expand(&self, p: $v) -> Self {
+                Self {
+                    min: self.min.min(p),
+                    max: self.max.max(p),
+                }
+            }
+
+            /// Moves every face outward by `margin`
+            pub fn grow(&self, margin: f32) -> Self {
+                let margin = <$v>::splat(margin);
+                Self {
+                    min: self.min - margin,
+                    max: self.max + margin,
+                }
+            }
+        }
+    };
+}
+
+AabbImpls!(Aabb2, Vec2);
+AabbImpls!(Aabb3, Vec3);
+
+impl Transformable for Aabb3 {
+    /// Bounding box of the transformed box (Arvo)
+    fn transform_mat4(&self, mat: &Mat4) -> Self {
+        let center = mat.transform_point3(self.center());
+        let half = 0.5 * self.size();
+        let [r0, r1, r2, _] = mat
+            .into_rows()
+            .map(|[x, y, z, _]| Vec3::from_components(x.abs(), y.abs(), z.abs()));
+        let extent = Vec3::from_components(r0.dot(half), r1.dot(half), r2.dot(half));
+        Self {
+            min: center - extent,
+            max: center + extent,
+        }
+    }
+}
+
+impl Transformable for Aabb2 {
+    /// The box lies in the z = 0 plane, the result is the bounding box of its transform projected
+    /// back onto that plane
+    fn transform_mat4(&self, mat: &Mat4) -> Self {
+        let [x0, y0] = self.min.into_array();
+        let [x1, y1] = self.max.into_array();
+        let aabb = Aabb3::new(
+            Vec3::from_components(x0, y0, 0.),
+            Vec3::from_components(x1, y1, 0.),
+        )
+        .transform_mat4(mat);
+        Self {
+            min: Vec2::from_components(aabb.min.x(), aabb.min.y()),
+            max: Vec2::from_components(aabb.max.x(), aabb.max.y()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub c: Vec3,
+}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3) -> Self {
+        Self { a, b, c }
+    }
+
+    /// Follows the right-hand rule, `None` for degenerate triangles
+    pub fn normal(&self) -> Option<Vec3> {
+        (self.b - self.a).cross(self.c - self.a).try_normalize()
+    }
+
+    pub fn area(&self) -> f32 {
+        0.5 * (self.b - self.a).cross(self.c - self.a).length()
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.a + self.b + self.c).scale(1. / 3.)
+    }
+
+    pub fn plane(&self) -> Option<Plane> {
+        Plane::from_points(self.a, self.b, self.c)
+    }
+
+    pub fn aabb(&self) -> Aabb3 {
+        Aabb3::from_point(self.a).expand(self.b).expand(self.c)
+    }
+}
+
+impl Transformable for Triangle {
+    fn transform_mat4(&self, mat: &Mat4) -> Self {
+        Self {
+            a: mat.transform_point3(self.a),
+            b: mat.transform_point3(self.b),
+            c: mat.transform_point3(self.c),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub a: Vec3,
+    pub b: Vec3,
+}
+
+impl Segment {
+    pub fn new(a: Vec3, b: Vec3) -> Self {
+        Self { a, b }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.a.distance(self.b)
+    }
+
+    /// `a` for 0 and `b` for 1
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.a.lerp(self.b, t)
+    }
+
+    pub fn aabb(&self) -> Aabb3 {
+        Aabb3::new(self.a, self.b)
+    }
+}
+
+impl Transformable for Segment {
+    fn transform_mat4(&self, mat: &Mat4) -> Self {
+        Self {
+            a: mat.transform_point3(self.a),
+            b: mat.transform_point3(self.b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aabb2, Aabb3, Plane, Sphere, Transformable, Triangle};
+    use crate::{
+        dim2::Vec2,
+        dim3::Vec3,
+        graphic::{Point3, Quaternion, Scale3, Transform},
+        Vector,
+    };
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!(a.distance(b) < 1e-5, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn bounds() {
+        let aabb = Aabb3::from_points([
+            Vec3::from_components(1., -2., 0.),
+            Vec3::from_components(-1., 3., 2.),
+            Vec3::from_components(0., 0., -1.),
+        ])
+        .unwrap();
+        assert_eq!(aabb.min, Vec3::from_components(-1., -2., -1.));
+        assert_eq!(aabb.max, Vec3::from_components(1., 3., 2.));
+        assert!(aabb.contains_point(Vec3::splat(0.)));
+        assert!(!aabb
+            .grow(-0.5)
+            .contains_point(Vec3::from_components(0., 0., 1.8)));
+        assert_eq!(Aabb3::from_points([]), None);
+
+        let a = Aabb2::new(Vec2::splat(1.), Vec2::splat(0.));
+        let b = a.union(&Aabb2::from_point(Vec2::from_components(3., -1.)));
+        assert_eq!(b.min, Vec2::from_components(0., -1.));
+        assert_eq!(b.size(), Vec2::from_components(3., 2.));
+
+        let s = Sphere::new(Vec3::splat(0.), 1.)
+            .merge(&Sphere::new(Vec3::from_components(4., 0., 0.), 1.));
+        assert_near(s.center, Vec3::from_components(2., 0., 0.));
+        assert_eq!(s.radius, 3.);
+        assert_eq!(s.merge(&Sphere::new(Vec3::splat(0.), 0.5)), s);
+    }
+
+    #[test]
+    fn transforms() {
+        let t = Transform {
+            position: Point3::from_components(1., 2., 3.),
+            scale: Scale3::from_components(2., 1., 1.),
+            rotation: Quaternion::from_axis_angle(
+                Vec3::from_components(0., 0., 1.),
+                std::f32::consts::FRAC_PI_2,
+            ),
+        };
+
+        let tri = Triangle::new(
+            Vec3::splat(0.),
+            Vec3::from_components(1., 0., 0.),
+            Vec3::from_components(0., 1., 0.),
+        );
+        let moved = tri.transform(&t);
+        for p in [moved.a, moved.b, moved.c] {
+            assert!(moved.aabb().contains_point(p));
+        }
+
+        // The plane of the triangle must follow its vertices even with non-uniform scaling
+        let plane = Plane::from_points(
+            tri.a,
+            Vec3::from_components(1., 0., 1.),
+            Vec3::from_components(0., 1., 0.),
+        )
+        .unwrap()
+        .transform(&t);
+        let scaled = Triangle::new(tri.a, Vec3::from_components(1., 0., 1.), tri.c).transform(&t);
+        for p in [scaled.a, scaled.b, scaled.c] {
+            assert!(plane.signed_distance(p).abs() < 1e-5);
+        }
+        assert_near(plane.normal, scaled.normal().unwrap());
+
+        let aabb = Aabb3::new(Vec3::splat(-1.), Vec3::splat(1.)).transform(&t);
+        assert_near(aabb.center(), Vec3::from_components(1., 2., 3.));
+        assert_near(aabb.size(), Vec3::from_components(4., 2., 2.));
+
+        let sphere = Sphere::new(Vec3::splat(0.), 1.).transform(&t);
+        assert!((sphere.radius - 2.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sphere_bounds_rotated_scaling() {
+        let t = Transform {
+            position: Point3::from_components(1., 2., 3.),
+            scale: Scale3::from_components(2., 1., 1.),
+            rotation: Quaternion::from_axis_angle(
+                Vec3::from_components(0., 0., 1.),
+                std::f32::consts::FRAC_PI_4,
+            ),
+        };
+        let sphere = Sphere::new(Vec3::splat(0.), 1.);
+        let moved = sphere.transform(&t);
+        for i in 0..64 {
+            let angle = i as f32 * std::f32::consts::TAU / 64.;
+            for p in [
+                Vec3::from_components(angle.cos(), angle.sin(), 0.),
+                Vec3::from_components(angle.cos(), 0., angle.sin()),
+                Vec3::from_components(0., angle.cos(), angle.sin()),
+            ] {
+                let p = t.as_mat4().transform_point3(p);
+                assert!(moved.center.distance(p) <= moved.radius + 1e-5);
+            }
+        }
+
+        // Rigid motions keep the radius
+        let rotation = Transform {
+            position: Point3::from_components(1., 2., 3.),
+            scale: Scale3::from_components(1., 1., 1.),
+            rotation: Quaternion::from_axis_angle(
+                Vec3::from_components(1., 2., 3.).normalize(),
+                1.2,
+            ),
+        };
+        for t in [Transform::new(), rotation] {
+            assert!((sphere.transform(&t).radius - 1.).abs() < 1e-5);
+        }
+        assert_eq!(sphere.transform(&Transform::new()).radius, 1.);
+    }
+}
@@ -1,6 +1,10 @@
-use std::simd::f32x4;
+use crate::simd::f32x4;
 
-use crate::{dim4::{Mat4, Vec4}, Vector, Matrix};
+use crate::{
+    dim3::Vec3,
+    dim4::{Mat4, Vec4},
+    Matrix, Vector,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Point3(Vec4);
@@ -12,6 +16,14 @@ impl Default for Point3 {
 }
 
 impl Point3 {
+    pub fn from_components(x: f32, y: f32, z: f32) -> Self {
+        Self(Vec4::from_components(x, y, z, 1.0))
+    }
+
+    pub fn as_vec3(self) -> Vec3 {
+        Vec3::from_components(self.0.x(), self.0.y(), self.0.z())
+    }
+
     pub fn translate(&mut self, x: f32, y: f32, z: f32) {
         self.0 = self.0 + Vec4::from_components(x, y, z, 0.0);
     }
@@ -26,6 +38,16 @@ impl Default for Scale3 {
     }
 }
 
+impl Scale3 {
+    pub fn from_components(x: f32, y: f32, z: f32) -> Self {
+        Self(Vec4::from_components(x, y, z, 0.0))
+    }
+
+    pub fn as_vec3(self) -> Vec3 {
+        Vec3::from_components(self.0.x(), self.0.y(), self.0.z())
+    }
+}
+
 impl std::ops::Mul<Mat4> for Scale3 {
     type Output = Mat4;
 
@@ -46,6 +68,25 @@ impl Default for Quaternion {
 }
 
 impl Quaternion {
+    // Stored as [w, x, y, z]
+    pub fn from_components(w: f32, x: f32, y: f32, z: f32) -> Self {
+        Self(Vec4::from_components(w, x, y, z))
+    }
+
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    pub fn w(self) -> f32 {
+        self.0[0]
+    }
+
+    /// Vector part
+    pub fn xyz(self) -> Vec3 {
+        let [_, x, y, z] = self.0.into_array();
+        Vec3::from_components(x, y, z)
+    }
+
     pub fn as_mat4(&self) -> Mat4 {
         // 12 mut + 11 add
         let [a, b, c, d] = self.0.into_array();
@@ -80,17 +121,224 @@ impl Quaternion {
         let (sy, cy) = f32::sin_cos(y / 2.);
         let (sz, cz) = f32::sin_cos(z / 2.);
 
-        Self(Vec4::from_array([
+        Self::from_components(
+            cx * cy * cz + sx * sy * sz,
             sx * cy * cz - cx * sy * sz,
             cx * sy * cz + sx * cy * sz,
             cx * cy * sz - sx * sy * cz,
-            cx * cy * cz + sx * sy * sz,
-        ]))
+        )
+    }
+
+    /// Inverse of [`Quaternion::from_euler`], the y angle is in `[-pi/2, pi/2]`
+    pub fn to_euler(self) -> (f32, f32, f32) {
+        let [w, x, y, z] = self.0.into_array();
+        let sin_y = (2. * (w * y - z * x)).clamp(-1., 1.);
+        (
+            f32::atan2(2. * (w * x + y * z), 1. - 2. * (x * x + y * y)),
+            f32::asin(sin_y),
+            f32::atan2(2. * (w * z + x * y), 1. - 2. * (y * y + z * z)),
+        )
+    }
+
+    /// `axis` is expected to be normalized
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        let (s, c) = f32::sin_cos(angle / 2.);
+        let [x, y, z] = axis.scale(s).into_array();
+        Self::from_components(c, x, y, z)
+    }
+
+    /// The angle is in `[0, 2pi]`, the axis is arbitrary for the identity
+    pub fn to_axis_angle(self) -> (Vec3, f32) {
+        let q = self.normalize();
+        let angle = 2. * q.w().clamp(-1., 1.).acos();
+        let axis = q
+            .xyz()
+            .try_normalize()
+            .unwrap_or(Vec3::from_components(1., 0., 0.));
+        (axis, angle)
+    }
+
+    /// Extracts the rotation of the upper left 3x3 block, which is expected to be orthonormal
+    pub fn from_mat4(mat: &Mat4) -> Self {
+        // Shepperd's method: start from the largest of w, x, y, z to stay away from 0
+        let [[m00, m01, m02, _], [m10, m11, m12, _], [m20, m21, m22, _], _] = mat.into_rows();
+        let trace = m00 + m11 + m22;
+        let q = if trace > 0. {
+            let s = 2. * (1. + trace).sqrt();
+            Self::from_components(s / 4., (m21 - m12) / s, (m02 - m20) / s, (m10 - m01) / s)
+        } else if m00 > m11 && m00 > m22 {
+            let s = 2. * (1. + m00 - m11 - m22).sqrt();
+            Self::from_components((m21 - m12) / s, s / 4., (m01 + m10) / s, (m02 + m20) / s)
+        } else if m11 > m22 {
+            let s = 2. * (1. + m11 - m00 - m22).sqrt();
+            Self::from_components((m02 - m20) / s, (m01 + m10) / s, s / 4., (m12 + m21) / s)
+        } else {
+            let s = 2. * (1. + m22 - m00 - m11).sqrt();
+            Self::from_components((m10 - m01) / s, (m02 + m20) / s, (m12 + m21) / s, s / 4.)
+        };
+        q.normalize()
+    }
+
+    /// Rotation sending -Z to `forward` and Y as close as possible to `up`, matching the camera
+    /// convention of [`PerspCamera`]. Returns `None` if `forward` is null or colinear with `up`.
+    pub fn look_rotation(forward: Vec3, up: Vec3) -> Option<Self> {
+        let z = -forward.try_normalize()?;
+        let x = up.cross(z).try_normalize()?;
+        let y = z.cross(x);
+
+        let [x0, x1, x2] = x.into_array();
+        let [y0, y1, y2] = y.into_array();
+        let [z0, z1, z2] = z.into_array();
+        Some(Self::from_mat4(&Mat4::from_rows(&[
+            [x0, y0, z0, 0.],
+            [x1, y1, z1, 0.],
+            [x2, y2, z2, 0.],
+            [0., 0., 0., 1.],
+        ])))
+    }
+
+    pub fn conjugate(self) -> Self {
+        let [w, x, y, z] = self.0.into_array();
+        Self::from_components(w, -x, -y, -z)
+    }
+
+    /// The result is not finite for a null quaternion
+    pub fn inverse(self) -> Self {
+        Self(self.conjugate().0.scale(1. / self.0.length_squared()))
+    }
+
+    pub fn normalize(self) -> Self {
+        Self(self.0.normalize())
+    }
+
+    pub fn dot(self, other: Self) -> f32 {
+        self.0.dot(other.0)
+    }
+
+    /// Rotates `v`, `self` is expected to be normalized
+    pub fn rotate_vec3(self, v: Vec3) -> Vec3 {
+        // v + 2w (u x v) + 2u x (u x v) with u the vector part
+        let u = self.xyz();
+        let t = 2. * u.cross(v);
+        v + self.w() * t + u.cross(t)
+    }
+
+    /// Rotates `p` around the origin, `self` is expected to be normalized
+    pub fn rotate_point(self, p: Point3) -> Point3 {
+        let [x, y, z] = self.rotate_vec3(p.as_vec3()).into_array();
+        Point3::from_components(x, y, z)
+    }
+
+    /// Normalized linear interpolation along the shortest path, cheaper than
+    /// [`Quaternion::slerp`] but without a constant angular velocity
+    pub fn nlerp(self, other: Self, t: f32) -> Self {
+        let other = if self.dot(other) < 0. { -other } else { other };
+        Self(self.0.lerp(other.0, t)).normalize()
+    }
+
+    /// Spherical linear interpolation along the shortest path, both quaternions are expected to be
+    /// normalized
+    pub fn slerp(self, other: Self, t: f32) -> Self {
+        if self.dot(other) < 0. {
+            self.slerp_unchecked(-other, t)
+        } else {
+            self.slerp_unchecked(other, t)
+        }
+    }
+
+    // Slerp without choosing the shortest path
+    fn slerp_unchecked(self, other: Self, t: f32) -> Self {
+        let cos_theta = self.dot(other);
+        if cos_theta.abs() > 1. - 1e-4 {
+            // sin(theta) is too small to divide by, the arc is almost a line
+            return Self(self.0.lerp(other.0, t)).normalize();
+        }
+        let theta = cos_theta.acos();
+        let inv_sin = 1. / theta.sin();
+        let a = ((1. - t) * theta).sin() * inv_sin;
+        let b = (t * theta).sin() * inv_sin;
+        Self(a * self.0 + b * other.0)
+    }
+
+    /// Spherical cubic interpolation between `self` and `other`, `a` and `b` are the control
+    /// points built by [`Quaternion::squad_control`] for `self` and `other` respectively
+    pub fn squad(self, other: Self, a: Self, b: Self, t: f32) -> Self {
+        self.slerp_unchecked(other, t)
+            .slerp_unchecked(a.slerp_unchecked(b, t), 2. * t * (1. - t))
+    }
+
+    /// Control point of `self` for [`Quaternion::squad`], given the previous and the next
+    /// keyframes
+    pub fn squad_control(self, prev: Self, next: Self) -> Self {
+        // q exp(-(log(q^-1 next) + log(q^-1 prev)) / 4)
+        let inv = self.conjugate();
+        let log_next = (inv * next).log();
+        let log_prev = (inv * prev).log();
+        self * Self(-0.25 * (log_next + log_prev)).exp()
+    }
+
+    // Logarithm of a unit quaternion, the result is a pure quaternion
+    fn log(self) -> Vec4 {
+        let [w, x, y, z] = self.0.into_array();
+        let v = Vec3::from_components(x, y, z);
+        let sin_theta = v.length();
+        if sin_theta < 1e-6 {
+            return Vec4::from_components(0., x, y, z);
+        }
+        let theta = f32::atan2(sin_theta, w);
+        let [x, y, z] = v.scale(theta / sin_theta).into_array();
+        Vec4::from_components(0., x, y, z)
+    }
+
+    // Exponential of a pure quaternion, the result is a unit quaternion
+    fn exp(self) -> Self {
+        let v = self.xyz();
+        let theta = v.length();
+        if theta < 1e-6 {
+            let [x, y, z] = v.into_array();
+            return Self::from_components(1., x, y, z).normalize();
+        }
+        let (s, c) = theta.sin_cos();
+        let [x, y, z] = v.scale(s / theta).into_array();
+        Self::from_components(c, x, y, z)
+    }
+}
+
+impl std::ops::Neg for Quaternion {
+    type Output = Self;
+
+    /// Same rotation, on the other side of the hypersphere
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+
+impl std::ops::Mul for Quaternion {
+    type Output = Self;
+
+    /// Hamilton product, `(a * b).rotate_vec3(v) == a.rotate_vec3(b.rotate_vec3(v))`
+    fn mul(self, rhs: Self) -> Self::Output {
+        let [a1, b1, c1, d1] = self.0.into_array();
+        let [a2, b2, c2, d2] = rhs.0.into_array();
+        Self::from_components(
+            a1 * a2 - b1 * b2 - c1 * c2 - d1 * d2,
+            a1 * b2 + b1 * a2 + c1 * d2 - d1 * c2,
+            a1 * c2 - b1 * d2 + c1 * a2 + d1 * b2,
+            a1 * d2 + b1 * c2 - c1 * b2 + d1 * a2,
+        )
+    }
+}
+
+impl std::ops::Mul<Vec3> for Quaternion {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Self::Output {
+        self.rotate_vec3(rhs)
     }
 }
 
 // First Translate then rotate and finally rotate
-#[derive(Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Transform {
     pub position: Point3,
     pub scale: Scale3,
@@ -111,13 +359,174 @@ impl Transform {
         mat[(2, 3)] = self.position.0[2];
         mat
     }
+
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        self.scale
+            .as_vec3()
+            .component_mul(self.rotation.rotate_vec3(v))
+    }
+
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        let [x, y, z] = (self.transform_vector(p.as_vec3()) + self.position.as_vec3()).into_array();
+        Point3::from_components(x, y, z)
+    }
+
+    /// World transform of `child` given the world transform of its `parent`.
+    ///
+    /// The scale is applied after the rotation, so the result is exact as long as the child scale
+    /// is uniform, otherwise the shear that a `Transform` cannot represent is dropped.
+    pub fn compose(parent: &Self, child: &Self) -> Self {
+        Self::with_rotation(
+            parent.as_mat4() * child.as_mat4(),
+            parent.rotation * child.rotation,
+        )
+    }
+
+    /// Returns `None` if a component of the scale is 0. Like [`Transform::compose`], the result
+    /// is only exact for uniform scales.
+    pub fn inverse(&self) -> Option<Self> {
+        Some(Self::with_rotation(
+            self.as_mat4().affine_inverse()?,
+            self.rotation.inverse(),
+        ))
+    }
+
+    // Closest transform to `mat` with the given rotation, the scale is the diagonal of S * R * R^T
+    fn with_rotation(mat: Mat4, rotation: Quaternion) -> Self {
+        let [a0, a1, a2, _] = mat.0.map(Vec4);
+        let [r0, r1, r2, _] = rotation.as_mat4().0.map(Vec4);
+        Self {
+            position: Point3::from_components(a0.w(), a1.w(), a2.w()),
+            scale: Scale3::from_components(a0.dot(r0), a1.dot(r1), a2.dot(r2)),
+            rotation,
+        }
+    }
+
+    /// Blends position and scale linearly and rotation spherically
+    pub fn interpolate(a: &Self, b: &Self, t: f32) -> Self {
+        Self {
+            position: Point3(a.position.0.lerp(b.position.0, t)),
+            scale: Scale3(a.scale.0.lerp(b.scale.0, t)),
+            rotation: a.rotation.slerp(b.rotation, t),
+        }
+    }
+}
+
+impl Mat4 {
+    /// Splits a matrix of the form built by [`Transform::as_mat4`] back into a [`Transform`].
+    ///
+    /// Returns `None` if the upper left 3x3 block is singular or contains a shear.
+    pub fn decompose(&self) -> Option<Transform> {
+        let [r0, r1, r2, _] = self.into_rows();
+        let rows = [r0, r1, r2].map(|[x, y, z, _]| Vec3::from_components(x, y, z));
+
+        let mut scale = rows.map(Vector::length);
+        if rows[0].dot(rows[1].cross(rows[2])) < 0. {
+            // Reflections are carried by the scale
+            scale[0] = -scale[0];
+        }
+        let [u0, u1, u2] = std::array::from_fn(|i| rows[i].scale(1. / scale[i]));
+        let orthonormal = [u0, u1, u2]
+            .iter()
+            .all(|u| u.into_array().into_iter().all(f32::is_finite))
+            && [u0.dot(u1), u1.dot(u2), u2.dot(u0)]
+                .iter()
+                .all(|d| d.abs() < 1e-4);
+        if !orthonormal {
+            return None;
+        }
+
+        let [[x0, x1, x2], [y0, y1, y2], [z0, z1, z2]] = [u0, u1, u2].map(Vec3::into_array);
+        let rotation = Quaternion::from_mat4(&Mat4::from_rows(&[
+            [x0, x1, x2, 0.],
+            [y0, y1, y2, 0.],
+            [z0, z1, z2, 0.],
+            [0., 0., 0., 1.],
+        ]));
+        let [sx, sy, sz] = scale;
+        Some(Transform {
+            position: Point3::from_components(r0[3], r1[3], r2[3]),
+            scale: Scale3::from_components(sx, sy, sz),
+            rotation,
+        })
+    }
+}
+
+impl Mat4 {
+    /// Right-handed view matrix: the camera at `eye` looks along `dir` with -Z.
+    ///
+    /// The result is not finite if `dir` is null or colinear with `up`.
+    pub fn look_to_rh(eye: Vec3, dir: Vec3, up: Vec3) -> Self {
+        let f = dir.normalize();
+        let s = f.cross(up).normalize();
+        let u = s.cross(f);
+        Self::view_from_axes(eye, s, u, -f)
+    }
+
+    /// Left-handed view matrix: the camera at `eye` looks along `dir` with +Z.
+    ///
+    /// The result is not finite if `dir` is null or colinear with `up`.
+    pub fn look_to_lh(eye: Vec3, dir: Vec3, up: Vec3) -> Self {
+        let f = dir.normalize();
+        let s = up.cross(f).normalize();
+        let u = f.cross(s);
+        Self::view_from_axes(eye, s, u, f)
+    }
+
+    /// Right-handed view matrix of a camera at `eye` looking at `target`
+    pub fn look_at_rh(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        Self::look_to_rh(eye, target - eye, up)
+    }
+
+    /// Left-handed view matrix of a camera at `eye` looking at `target`
+    pub fn look_at_lh(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        Self::look_to_lh(eye, target - eye, up)
+    }
+
+    // | X^T -X.eye |
+    // | Y^T -Y.eye |
+    // | Z^T -Z.eye |
+    // |  0     1   |
+    fn view_from_axes(eye: Vec3, x: Vec3, y: Vec3, z: Vec3) -> Self {
+        let row = |a: Vec3| {
+            let [a0, a1, a2] = a.into_array();
+            [a0, a1, a2, -a.dot(eye)]
+        };
+        Self::from_rows(&[row(x), row(y), row(z), [0., 0., 0., 1.]])
+    }
+}
+
+/// Depth of the near and far planes in normalized device coordinates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DepthRange {
+    /// OpenGL convention, near at -1 and far at 1
+    NegativeOneToOne,
+    /// Vulkan and Direct3D convention, near at 0 and far at 1
+    #[default]
+    ZeroToOne,
+    /// Near at 1 and far at 0, spreads the float precision more evenly over the depth
+    ReverseZ,
 }
 
+impl DepthRange {
+    fn near_far(self) -> (f32, f32) {
+        match self {
+            DepthRange::NegativeOneToOne => (-1., 1.),
+            DepthRange::ZeroToOne => (0., 1.),
+            DepthRange::ReverseZ => (1., 0.),
+        }
+    }
+}
+
+/// Right-handed perspective projection, the camera looks down -Z
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PerspCamera {
     pub aspect_ratio: f32,
     pub fov: f32,
+    /// May be `f32::INFINITY`
     pub far: f32,
     pub near: f32,
+    pub depth_range: DepthRange,
 }
 
 impl PerspCamera {
@@ -127,6 +536,19 @@ impl PerspCamera {
             fov,
             far,
             near,
+            depth_range: DepthRange::default(),
+        }
+    }
+
+    /// Perspective without far plane
+    pub fn infinite(aspect_ratio: f32, fov: f32, near: f32) -> Self {
+        Self::new(aspect_ratio, fov, f32::INFINITY, near)
+    }
+
+    pub fn with_depth_range(self, depth_range: DepthRange) -> Self {
+        Self {
+            depth_range,
+            ..self
         }
     }
 
@@ -134,8 +556,14 @@ impl PerspCamera {
         let cotan_half_fov = 1. / f32::tan(self.fov / 2.);
         let m = cotan_half_fov / self.aspect_ratio;
         let l = cotan_half_fov;
-        let q = -self.far / (self.far - self.near);
-        let q2 = q * self.near;
+        // z_ndc = (q z + q2) / -z maps -near to d_near and -far to d_far
+        let (d_near, d_far) = self.depth_range.near_far();
+        let q = if self.far.is_infinite() {
+            -d_far
+        } else {
+            (d_near * self.near - d_far * self.far) / (self.far - self.near)
+        };
+        let q2 = (d_near + q) * self.near;
         Mat4::from_rows(&[
             [m, 0., 0., 0.],
             [0., l, 0., 0.],
@@ -144,3 +572,496 @@ impl PerspCamera {
         ])
     }
 }
+
+/// Right-handed orthographic projection, the camera looks down -Z
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrthoCamera {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+    pub near: f32,
+    pub far: f32,
+    pub depth_range: DepthRange,
+}
+
+impl OrthoCamera {
+    pub fn new(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        Self {
+            left,
+            right,
+            bottom,
+            top,
+            near,
+            far,
+            depth_range: DepthRange::default(),
+        }
+    }
+
+    /// View volume centered on the Z axis
+    pub fn symmetric(width: f32, height: f32, near: f32, far: f32) -> Self {
+        Self::new(
+            -width / 2.,
+            width / 2.,
+            -height / 2.,
+            height / 2.,
+            near,
+            far,
+        )
+    }
+
+    pub fn with_depth_range(self, depth_range: DepthRange) -> Self {
+        Self {
+            depth_range,
+            ..self
+        }
+    }
+
+    pub fn as_mat4(&self) -> Mat4 {
+        let width = self.right - self.left;
+        let height = self.top - self.bottom;
+        // z_ndc = q z + q2 maps -near to d_near and -far to d_far
+        let (d_near, d_far) = self.depth_range.near_far();
+        let q = (d_near - d_far) / (self.far - self.near);
+        let q2 = d_near + q * self.near;
+        Mat4::from_rows(&[
+            [2. / width, 0., 0., -(self.right + self.left) / width],
+            [0., 2. / height, 0., -(self.top + self.bottom) / height],
+            [0., 0., q, q2],
+            [0., 0., 0., 1.],
+        ])
+    }
+}
+
+/// Camera looking down its local -Z axis, the scale of the transform is ignored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub transform: Transform,
+    pub projection: PerspCamera,
+}
+
+impl Camera {
+    pub fn new(transform: Transform, projection: PerspCamera) -> Self {
+        Self {
+            transform,
+            projection,
+        }
+    }
+
+    /// World to camera space
+    pub fn view(&self) -> Mat4 {
+        let inv_rotation = self.transform.rotation.conjugate();
+        let mut mat = inv_rotation.as_mat4();
+        let [x, y, z] = inv_rotation
+            .rotate_vec3(-self.transform.position.as_vec3())
+            .into_array();
+        mat[(0, 3)] = x;
+        mat[(1, 3)] = y;
+        mat[(2, 3)] = z;
+        mat
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        self.projection.as_mat4() * self.view()
+    }
+}
+
+// Keeps the cameras from flipping over the poles
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 1e-3;
+
+// Yaw around Y then pitch around X
+fn yaw_pitch_rotation(yaw: f32, pitch: f32) -> Quaternion {
+    Quaternion::from_axis_angle(Vec3::from_components(0., 1., 0.), yaw)
+        * Quaternion::from_axis_angle(Vec3::from_components(1., 0., 0.), pitch)
+}
+
+/// Camera turning around `target` at `distance`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl OrbitController {
+    pub fn new(target: Vec3, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            yaw: 0.,
+            pitch: 0.,
+        }
+    }
+
+    /// A positive `zoom_delta` moves the camera closer, `distance` is scaled by
+    /// `exp(-zoom_delta)` so it never reaches the target
+    pub fn update(&mut self, yaw_delta: f32, pitch_delta: f32, zoom_delta: f32) {
+        self.yaw += yaw_delta;
+        self.pitch = (self.pitch + pitch_delta).clamp(-MAX_PITCH, MAX_PITCH);
+        self.distance *= (-zoom_delta).exp();
+    }
+
+    pub fn transform(&self) -> Transform {
+        let rotation = yaw_pitch_rotation(self.yaw, self.pitch);
+        let [x, y, z] =
+            (self.target + rotation * Vec3::from_components(0., 0., self.distance)).into_array();
+        Transform {
+            position: Point3::from_components(x, y, z),
+            rotation,
+            ..Default::default()
+        }
+    }
+}
+
+/// First person camera
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlyController {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl FlyController {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            yaw: 0.,
+            pitch: 0.,
+        }
+    }
+
+    /// `movement` is expressed in the camera space: X right, Y up and -Z forward
+    pub fn update(&mut self, yaw_delta: f32, pitch_delta: f32, movement: Vec3) {
+        self.yaw += yaw_delta;
+        self.pitch = (self.pitch + pitch_delta).clamp(-MAX_PITCH, MAX_PITCH);
+        self.position = self.position + yaw_pitch_rotation(self.yaw, self.pitch) * movement;
+    }
+
+    pub fn transform(&self) -> Transform {
+        let [x, y, z] = self.position.into_array();
+        Transform {
+            position: Point3::from_components(x, y, z),
+            rotation: yaw_pitch_rotation(self.yaw, self.pitch),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Camera, DepthRange, FlyController, Mat4, OrbitController, OrthoCamera, PerspCamera, Point3,
+        Quaternion, Scale3, Transform,
+    };
+    use crate::{dim3::Vec3, dim4::Vec4, Matrix, Vector};
+
+    fn assert_near(a: &[f32], b: &[f32]) {
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-5, "{a:?} != {b:?}");
+        }
+    }
+
+    fn assert_same_rotation(a: Quaternion, b: Quaternion) {
+        // q and -q are the same rotation
+        assert!((a.dot(b).abs() - 1.).abs() < 1e-5, "{a:?} != {b:?}");
+    }
+
+    fn samples() -> [Quaternion; 5] {
+        [
+            Quaternion::identity(),
+            Quaternion::from_euler(0.3, -0.7, 1.2),
+            Quaternion::from_euler(3., 0.1, -2.5),
+            Quaternion::from_axis_angle(Vec3::from_components(0., 1., 0.), std::f32::consts::PI),
+            Quaternion::from_axis_angle(Vec3::from_components(0., 0.6, 0.8), 2.),
+        ]
+    }
+
+    #[test]
+    fn rotate_matches_mat4() {
+        let v = Vec3::from_components(1., -2., 3.);
+        for q in samples() {
+            let rotated = q.as_mat4() * Vec4::from_components(v.x(), v.y(), v.z(), 0.);
+            assert_near(&(q * v).into_array(), &rotated.into_array()[..3]);
+        }
+
+        let q = Quaternion::from_axis_angle(
+            Vec3::from_components(0., 0., 1.),
+            0.5 * std::f32::consts::PI,
+        );
+        let p = q.rotate_point(Point3::from_components(1., 0., 0.));
+        assert_near(&p.as_vec3().into_array(), &[0., 1., 0.]);
+    }
+
+    #[test]
+    fn hamilton_product() {
+        for a in samples() {
+            for b in samples() {
+                assert_near(
+                    &(a * b).as_mat4().into_array(),
+                    &(a.as_mat4() * b.as_mat4()).into_array(),
+                );
+            }
+            assert_same_rotation(a * a.inverse(), Quaternion::identity());
+            assert_same_rotation(a.conjugate(), a.inverse());
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        for q in samples() {
+            assert_same_rotation(Quaternion::from_mat4(&q.as_mat4()), q);
+
+            let (axis, angle) = q.to_axis_angle();
+            assert_same_rotation(Quaternion::from_axis_angle(axis, angle), q);
+
+            let (x, y, z) = q.to_euler();
+            assert_same_rotation(Quaternion::from_euler(x, y, z), q);
+        }
+        assert_eq!(
+            Mat4::identity().into_rows(),
+            Quaternion::identity().as_mat4().into_rows()
+        );
+    }
+
+    #[test]
+    fn slerp() {
+        let axis = Vec3::from_components(0., 0.6, 0.8);
+        let a = Quaternion::from_axis_angle(axis, 0.2);
+        let b = Quaternion::from_axis_angle(axis, 1.4);
+        assert_same_rotation(a.slerp(b, 0.), a);
+        assert_same_rotation(a.slerp(b, 1.), b);
+        assert_same_rotation(a.slerp(b, 0.25), Quaternion::from_axis_angle(axis, 0.5));
+        assert_same_rotation(a.slerp(-b, 0.25), Quaternion::from_axis_angle(axis, 0.5));
+        assert_same_rotation(a.nlerp(b, 0.5), Quaternion::from_axis_angle(axis, 0.8));
+        assert_same_rotation(a.slerp(a, 0.7), a);
+    }
+
+    #[test]
+    fn squad() {
+        let keys = [0.1, 0.9, 1.5, 2.8]
+            .map(|angle| Quaternion::from_axis_angle(Vec3::from_components(1., 0., 0.), angle));
+        let s1 = keys[1].squad_control(keys[0], keys[2]);
+        let s2 = keys[2].squad_control(keys[1], keys[3]);
+        assert_same_rotation(keys[1].squad(keys[2], s1, s2, 0.), keys[1]);
+        assert_same_rotation(keys[1].squad(keys[2], s1, s2, 1.), keys[2]);
+
+        let (axis, angle) = keys[1].squad(keys[2], s1, s2, 0.5).to_axis_angle();
+        assert_near(&axis.into_array(), &[1., 0., 0.]);
+        assert!(0.9 < angle && angle < 1.5);
+    }
+
+    #[test]
+    fn interpolate_transform() {
+        let mut a = Transform::new();
+        a.position.translate(1., 2., 3.);
+        let mut b = a;
+        b.position.translate(2., 0., 0.);
+        b.rotation = Quaternion::from_axis_angle(Vec3::from_components(0., 1., 0.), 1.);
+
+        let mid = Transform::interpolate(&a, &b, 0.5);
+        assert_eq!(mid.position, Point3::from_components(2., 2., 3.));
+        assert_eq!(mid.scale, a.scale);
+        assert_same_rotation(
+            mid.rotation,
+            Quaternion::from_axis_angle(Vec3::from_components(0., 1., 0.), 0.5),
+        );
+        assert_eq!(Transform::interpolate(&a, &b, 1.).position, b.position);
+    }
+
+    fn sample_transform(scale: Scale3) -> Transform {
+        let mut t = Transform {
+            scale,
+            rotation: Quaternion::from_euler(0.3, -1.1, 2.),
+            ..Default::default()
+        };
+        t.position.translate(1., -2., 3.);
+        t
+    }
+
+    #[test]
+    fn decompose() {
+        for scale in [
+            Scale3::from_components(2., 2., 2.),
+            Scale3::from_components(1., 3., 0.5),
+            Scale3::from_components(-1., 3., 0.5),
+        ] {
+            let t = sample_transform(scale);
+            let d = t.as_mat4().decompose().unwrap();
+            assert_near(
+                &d.position.as_vec3().into_array(),
+                &t.position.as_vec3().into_array(),
+            );
+            assert_near(
+                &d.scale.as_vec3().into_array(),
+                &t.scale.as_vec3().into_array(),
+            );
+            assert_same_rotation(d.rotation, t.rotation);
+        }
+        assert!(Mat4::from_rows(&[[0.; 4]; 4]).decompose().is_none());
+        let shear = Mat4::from_rows(&[
+            [1., 1., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ]);
+        assert!(shear.decompose().is_none());
+    }
+
+    #[test]
+    fn hierarchy() {
+        let parent = sample_transform(Scale3::from_components(1., 3., 0.5));
+        let mut child = Transform {
+            scale: Scale3::from_components(2., 2., 2.),
+            rotation: Quaternion::from_euler(-0.4, 0.2, 0.9),
+            ..Default::default()
+        };
+        child.position.translate(0.5, 4., -1.);
+
+        let world = Transform::compose(&parent, &child);
+        assert_near(
+            &world.as_mat4().into_array(),
+            &(parent.as_mat4() * child.as_mat4()).into_array(),
+        );
+
+        let p = Point3::from_components(3., 1., -2.);
+        let expected = world.as_mat4() * Vec4::from_components(3., 1., -2., 1.);
+        assert_near(
+            &world.transform_point(p).as_vec3().into_array(),
+            &expected.into_array()[..3],
+        );
+
+        // Inverses are exact for uniform scales
+        let uniform = Transform::compose(&child, &child);
+        assert_near(
+            &uniform
+                .inverse()
+                .unwrap()
+                .transform_point(uniform.transform_point(p))
+                .as_vec3()
+                .into_array(),
+            &p.as_vec3().into_array(),
+        );
+        assert_near(
+            &Transform::compose(&uniform, &uniform.inverse().unwrap())
+                .as_mat4()
+                .into_array(),
+            &Mat4::identity().into_array(),
+        );
+    }
+
+    #[test]
+    fn look_rotation() {
+        let forward = Vec3::from_components(1., 0., -1.).normalize();
+        let up = Vec3::from_components(0., 1., 0.);
+        let q = Quaternion::look_rotation(forward, up).unwrap();
+        assert_near(
+            &(q * Vec3::from_components(0., 0., -1.)).into_array(),
+            &forward.into_array(),
+        );
+        assert_near(&(q * up).into_array(), &up.into_array());
+        assert!(Quaternion::look_rotation(up, up).is_none());
+    }
+
+    fn transform_point(mat: Mat4, p: Vec3) -> [f32; 3] {
+        let [x, y, z, w] = (mat * Vec4::from_components(p.x(), p.y(), p.z(), 1.)).into_array();
+        [x / w, y / w, z / w]
+    }
+
+    #[test]
+    fn look_at() {
+        let eye = Vec3::from_components(1., 2., 3.);
+        let target = Vec3::from_components(1., 2., -1.);
+        let up = Vec3::from_components(0., 1., 0.);
+
+        let rh = Mat4::look_at_rh(eye, target, up);
+        assert_near(&transform_point(rh, eye), &[0., 0., 0.]);
+        assert_near(&transform_point(rh, target), &[0., 0., -4.]);
+        assert_near(&transform_point(rh, eye + up), &[0., 1., 0.]);
+
+        let lh = Mat4::look_at_lh(eye, target, up);
+        assert_near(&transform_point(lh, target), &[0., 0., 4.]);
+        assert_near(&transform_point(lh, eye + up), &[0., 1., 0.]);
+    }
+
+    #[test]
+    fn camera_view() {
+        let eye = Vec3::from_components(4., -1., 2.);
+        let forward = Vec3::from_components(-1., 0.5, -2.);
+        let up = Vec3::from_components(0., 1., 0.);
+        let [x, y, z] = eye.into_array();
+        let camera = Camera::new(
+            Transform {
+                position: Point3::from_components(x, y, z),
+                rotation: Quaternion::look_rotation(forward, up).unwrap(),
+                ..Default::default()
+            },
+            PerspCamera::new(1.5, 1., 100., 0.1),
+        );
+        assert_near(
+            &camera.view().into_array(),
+            &Mat4::look_to_rh(eye, forward, up).into_array(),
+        );
+        assert_near(
+            &camera.view_projection().into_array(),
+            &(camera.projection.as_mat4() * camera.view()).into_array(),
+        );
+    }
+
+    #[test]
+    fn controllers() {
+        let target = Vec3::from_components(1., 0., 1.);
+        let mut orbit = OrbitController::new(target, 5.);
+        orbit.update(0.7, 0.3, 0.);
+        orbit.update(0., 10., 2f32.ln());
+        assert!(orbit.pitch < std::f32::consts::FRAC_PI_2);
+        let view = Camera::new(orbit.transform(), PerspCamera::new(1., 1., 10., 1.)).view();
+        assert_near(&transform_point(view, target), &[0., 0., -2.5]);
+
+        let mut fly = FlyController::new(Vec3::splat(0.));
+        fly.update(
+            std::f32::consts::FRAC_PI_2,
+            0.,
+            Vec3::from_components(0., 0., -1.),
+        );
+        assert_near(&fly.position.into_array(), &[-1., 0., 0.]);
+        fly.update(0., 0., Vec3::from_components(1., 0., 0.));
+        assert_near(&fly.position.into_array(), &[-1., 0., -1.]);
+    }
+
+    fn ndc_depth(projection: Mat4, z: f32) -> f32 {
+        transform_point(projection, Vec3::from_components(0., 0., z))[2]
+    }
+
+    #[test]
+    fn depth_ranges() {
+        for (range, near, far) in [
+            (DepthRange::NegativeOneToOne, -1., 1.),
+            (DepthRange::ZeroToOne, 0., 1.),
+            (DepthRange::ReverseZ, 1., 0.),
+        ] {
+            let persp = PerspCamera::new(1.5, 1.2, 50., 0.5).with_depth_range(range);
+            assert_near(&[ndc_depth(persp.as_mat4(), -0.5)], &[near]);
+            assert_near(&[ndc_depth(persp.as_mat4(), -50.)], &[far]);
+
+            let infinite = PerspCamera::infinite(1.5, 1.2, 0.5).with_depth_range(range);
+            assert_near(&[ndc_depth(infinite.as_mat4(), -0.5)], &[near]);
+            assert!((ndc_depth(infinite.as_mat4(), -1e6) - far).abs() < 1e-4);
+
+            let ortho = OrthoCamera::symmetric(4., 2., 1., 11.).with_depth_range(range);
+            assert_near(&[ndc_depth(ortho.as_mat4(), -1.)], &[near]);
+            assert_near(&[ndc_depth(ortho.as_mat4(), -11.)], &[far]);
+            assert_near(&[ndc_depth(ortho.as_mat4(), -6.)], &[(near + far) / 2.]);
+        }
+
+        let ortho = OrthoCamera::new(0., 4., -1., 1., 1., 11.).as_mat4();
+        assert_near(
+            &transform_point(ortho, Vec3::from_components(4., 1., -1.)),
+            &[1., 1., 0.],
+        );
+        assert_near(
+            &transform_point(ortho, Vec3::from_components(0., -1., -1.)),
+            &[-1., -1., 0.],
+        );
+    }
+}
@@ -0,0 +1,500 @@
+use crate::{
+    dim2::Vec2,
+    dim3::Vec3,
+    geometry::{Aabb2, Aabb3, Plane, Ray, Segment, Sphere, Triangle},
+    Vector,
+};
+
+// Below this, a ray is considered parallel to a plane or a triangle
+const PARALLEL_EPSILON: f32 = 1e-7;
+
+/// First point where a ray crosses the surface of a shape
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// Distance along the ray, `ray.at(distance) == point`
+    pub distance: f32,
+    pub point: Vec3,
+    /// Unit normal of the surface at `point`
+    pub normal: Vec3,
+}
+
+/// Overlap between two solids
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub point: Vec3,
+    /// Unit vector pointing from the first shape toward the second one, moving the second shape
+    /// by `depth * normal` separates them
+    pub normal: Vec3,
+    /// Penetration depth, 0 when the shapes only touch
+    pub depth: f32,
+}
+
+impl Ray {
+    fn hit(&self, distance: f32, normal: Vec3) -> RayHit {
+        RayHit {
+            distance,
+            point: self.at(distance),
+            normal,
+        }
+    }
+
+    /// The normal faces the origin of the ray
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<RayHit> {
+        let denom = plane.normal.dot(self.dir);
+        if denom.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+        let t = -plane.signed_distance(self.origin) / denom;
+        let normal = if denom > 0. {
+            -plane.normal
+        } else {
+            plane.normal
+        };
+        (t >= 0.).then(|| self.hit(t, normal))
+    }
+
+    /// Möller–Trumbore, both faces are hit and the normal faces the origin of the ray
+    pub fn intersect_triangle(&self, triangle: &Triangle) -> Option<RayHit> {
+        let e1 = triangle.b - triangle.a;
+        let e2 = triangle.c - triangle.a;
+        let p = self.dir.cross(e2);
+        let det = e1.dot(p);
+        if det.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+        let inv_det = 1. / det;
+
+        let s = self.origin - triangle.a;
+        let u = s.dot(p) * inv_det;
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+        let q = s.cross(e1);
+        let v = self.dir.dot(q) * inv_det;
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+        let t = e2.dot(q) * inv_det;
+        if t < 0. {
+            return None;
+        }
+
+        let normal = e1.cross(e2).normalize();
+        let normal = if det < 0. { -normal } else { normal };
+        Some(self.hit(t, normal))
+    }
+
+    /// When the origin is inside the sphere, the exit point is returned
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<RayHit> {
+        let oc = self.origin - sphere.center;
+        let b = oc.dot(self.dir);
+        let c = oc.length_squared() - sphere.radius * sphere.radius;
+        let discriminant = b * b - c;
+        if discriminant < 0. {
+            return None;
+        }
+        let sq = discriminant.sqrt();
+        let t = if -b - sq >= 0. { -b - sq } else { -b + sq };
+        if t < 0. {
+            return None;
+        }
+        let point = self.at(t);
+        Some(RayHit {
+            distance: t,
+            point,
+            normal: (point - sphere.center).scale(1. / sphere.radius),
+        })
+    }
+
+    /// Slab test, when the origin is inside the box the exit point is returned
+    pub fn intersect_aabb(&self, aabb: &Aabb3) -> Option<RayHit> {
+        let (t_enter, t_exit) = aabb.intersect_ray(self.origin, self.dir)?;
+        let (t, entering) = if t_enter >= 0. {
+            (t_enter, true)
+        } else {
+            (t_exit, false)
+        };
+
+        // The hit face is the one the point lies on, the entry face looks against the ray and the
+        // exit face along it
+        let point = self.at(t).into_array();
+        let dir = self.dir.into_array();
+        let (min, max) = (aabb.min.into_array(), aabb.max.into_array());
+        let face_distance = |i: usize| {
+            let face = if (dir[i] > 0.) == entering {
+                min[i]
+            } else {
+                max[i]
+            };
+            (point[i] - face).abs()
+        };
+        let axis = (0..3)
+            .filter(|&i| dir[i] != 0.)
+            .min_by(|&a, &b| face_distance(a).total_cmp(&face_distance(b)))?;
+        let mut normal = Vec3::splat(0.);
+        normal[axis] = if entering {
+            -dir[axis].signum()
+        } else {
+            dir[axis].signum()
+        };
+        Some(self.hit(t, normal))
+    }
+
+    /// Closest point of the ray, which does not extend behind its origin
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        self.at((p - self.origin).dot(self.dir).max(0.))
+    }
+
+    pub fn distance_to_point(&self, p: Vec3) -> f32 {
+        self.closest_point(p).distance(p)
+    }
+}
+
+impl Sphere {
+    /// Points inside the ball are their own closest point
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        if self.contains_point(p) {
+            p
+        } else {
+            self.center + self.radius * (p - self.center).normalize()
+        }
+    }
+
+    /// 0 inside the ball
+    pub fn distance_to_point(&self, p: Vec3) -> f32 {
+        (self.center.distance(p) - self.radius).max(0.)
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb3) -> bool {
+        aabb.distance_squared_to_point(self.center) <= self.radius * self.radius
+    }
+
+    /// The contact point is on the surface of the box
+    pub fn contact_aabb(&self, aabb: &Aabb3) -> Option<Contact> {
+        let closest = aabb.closest_point(self.center);
+        let distance_squared = closest.distance_squared(self.center);
+        if distance_squared > self.radius * self.radius {
+            return None;
+        }
+        if distance_squared > 0. {
+            let distance = distance_squared.sqrt();
+            return Some(Contact {
+                point: closest,
+                normal: (closest - self.center).scale(1. / distance),
+                depth: self.radius - distance,
+            });
+        }
+
+        // The center is inside the box, push the sphere through the nearest face
+        let center = self.center.into_array();
+        let (min, max) = (aabb.min.into_array(), aabb.max.into_array());
+        let (mut face_distance, mut axis, mut sign) = (f32::INFINITY, 0, 1.);
+        for i in 0..3 {
+            if center[i] - min[i] < face_distance {
+                (face_distance, axis, sign) = (center[i] - min[i], i, 1.);
+            }
+            if max[i] - center[i] < face_distance {
+                (face_distance, axis, sign) = (max[i] - center[i], i, -1.);
+            }
+        }
+        let mut normal = Vec3::splat(0.);
+        normal[axis] = sign;
+        let mut point = self.center;
+        point[axis] -= sign * face_distance;
+        Some(Contact {
+            point,
+            normal,
+            depth: self.radius + face_distance,
+        })
+    }
+}
+
+macro_rules! AabbQueries {
+    ($aabb: ident, $v: ident) => {
+        impl $aabb {
+            /// Points inside the box are their own closest point
+            pub fn closest_point(&self, p: $v) -> $v {
+                p.max(self.min).min(self.max)
+            }
+
+            pub fn distance_squared_to_point(&self, p: $v) -> f32 {
+                self.closest_point(p).distance_squared(p)
+            }
+
+            /// 0 inside the box
+            pub fn distance_to_point(&self, p: $v) -> f32 {
+                self.closest_point(p).distance(p)
+            }
+
+            /// Distances along the line `origin + t * dir` at which it enters and leaves the box,
+            /// `None` if the ray, for `t >= 0`, misses it. The entry distance is negative when
+            /// `origin` is inside the box.
+            pub fn intersect_ray(&self, origin: $v, dir: $v) -> Option<(f32, f32)> {
+                let (origin, dir) = (origin.into_array(), dir.into_array());
+                let (min, max) = (self.min.into_array(), self.max.into_array());
+                let (mut t_enter, mut t_exit) = (f32::NEG_INFINITY, f32::INFINITY);
+                for i in 0..origin.len() {
+                    // Parallel to the slab, which would give NaN distances when `origin` is on
+                    // its border
+                    if dir[i] == 0. {
+                        if origin[i] < min[i] || max[i] < origin[i] {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let inv_dir = 1. / dir[i];
+                    let t0 = (min[i] - origin[i]) * inv_dir;
+                    let t1 = (max[i] - origin[i]) * inv_dir;
+                    t_enter = t_enter.max(t0.min(t1));
+                    t_exit = t_exit.min(t0.max(t1));
+                }
+                (t_enter <= t_exit && t_exit >= 0.).then_some((t_enter, t_exit))
+            }
+
+            /// Boxes sharing only a face or an edge intersect
+            pub fn intersects(&self, other: &Self) -> bool {
+                let (min, max) = (self.min.max(other.min), self.max.min(other.max));
+                let (min, max) = (min.into_array(), max.into_array());
+                (0..min.len()).all(|i| min[i] <= max[i])
+            }
+        }
+    };
+}
+
+AabbQueries!(Aabb2, Vec2);
+AabbQueries!(Aabb3, Vec3);
+
+impl Aabb3 {
+    /// Separates the boxes along the axis of least overlap, the contact point is the center of
+    /// the overlapping region
+    pub fn contact(&self, other: &Self) -> Option<Contact> {
+        let overlap = self.max.min(other.max) - self.min.max(other.min);
+        let [x, y, z] = overlap.into_array();
+        if x < 0. || y < 0. || z < 0. {
+            return None;
+        }
+        let axis = if x <= y && x <= z {
+            0
+        } else if y <= z {
+            1
+        } else {
+            2
+        };
+        let mut normal = Vec3::splat(0.);
+        normal[axis] = if other.center()[axis] >= self.center()[axis] {
+            1.
+        } else {
+            -1.
+        };
+        Some(Contact {
+            point: self.min.max(other.min) + 0.5 * overlap,
+            normal,
+            depth: overlap[axis],
+        })
+    }
+}
+
+impl Triangle {
+    /// Closest point of the filled triangle (Ericson, Real-Time Collision Detection 5.1.5)
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        let Self { a, b, c } = *self;
+        let ab = b - a;
+        let ac = c - a;
+
+        let ap = p - a;
+        let d1 = ab.dot(ap);
+        let d2 = ac.dot(ap);
+        if d1 <= 0. && d2 <= 0. {
+            return a;
+        }
+
+        let bp = p - b;
+        let d3 = ab.dot(bp);
+        let d4 = ac.dot(bp);
+        if d3 >= 0. && d4 <= d3 {
+            return b;
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0. && d1 >= 0. && d3 <= 0. {
+            return a + (d1 / (d1 - d3)) * ab;
+        }
+
+        let cp = p - c;
+        let d5 = ab.dot(cp);
+        let d6 = ac.dot(cp);
+        if d6 >= 0. && d5 <= d6 {
+            return c;
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0. && d2 >= 0. && d6 <= 0. {
+            return a + (d2 / (d2 - d6)) * ac;
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+            return b + ((d4 - d3) / ((d4 - d3) + (d5 - d6))) * (c - b);
+        }
+
+        // Inside the face, barycentric coordinates
+        let denom = 1. / (va + vb + vc);
+        a + (vb * denom) * ab + (vc * denom) * ac
+    }
+
+    pub fn distance_to_point(&self, p: Vec3) -> f32 {
+        self.closest_point(p).distance(p)
+    }
+}
+
+impl Segment {
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        let ab = self.b - self.a;
+        let length_squared = ab.length_squared();
+        if length_squared == 0. {
+            return self.a;
+        }
+        self.at(((p - self.a).dot(ab) / length_squared).clamp(0., 1.))
+    }
+
+    pub fn distance_to_point(&self, p: Vec3) -> f32 {
+        self.closest_point(p).distance(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Contact;
+    use crate::{
+        dim2::Vec2,
+        dim3::Vec3,
+        geometry::{Aabb2, Aabb3, Plane, Ray, Segment, Sphere, Triangle},
+        Vector,
+    };
+
+    fn v(x: f32, y: f32, z: f32) -> Vec3 {
+        Vec3::from_components(x, y, z)
+    }
+
+    #[test]
+    fn ray_casts() {
+        let ray = Ray::new(v(0.2, 0.2, 5.), v(0., 0., -2.));
+
+        let triangle = Triangle::new(v(0., 0., 1.), v(1., 0., 1.), v(0., 1., 1.));
+        let hit = ray.intersect_triangle(&triangle).unwrap();
+        assert!((hit.distance - 4.).abs() < 1e-6);
+        assert_eq!(hit.normal, v(0., 0., 1.));
+        let flipped = Triangle::new(triangle.a, triangle.c, triangle.b);
+        assert_eq!(ray.intersect_triangle(&flipped).unwrap().normal, hit.normal);
+        assert_eq!(
+            Ray::new(v(0.8, 0.8, 5.), v(0., 0., -1.)).intersect_triangle(&triangle),
+            None
+        );
+
+        let plane = Plane::from_point_normal(v(0., 0., -1.), v(0., 0., -1.));
+        let hit = ray.intersect_plane(&plane).unwrap();
+        assert_eq!(hit.point, v(0.2, 0.2, -1.));
+        assert_eq!(hit.normal, v(0., 0., 1.));
+        assert_eq!(ray.intersect_plane(&plane.flip()), Some(hit));
+
+        let sphere = Sphere::new(v(0.2, 0.2, 0.), 2.);
+        let hit = ray.intersect_sphere(&sphere).unwrap();
+        assert_eq!(hit.distance, 3.);
+        assert_eq!(hit.normal, v(0., 0., 1.));
+        let inside = Ray::new(sphere.center, v(0., 0., -1.));
+        assert_eq!(
+            inside.intersect_sphere(&sphere).unwrap().normal,
+            v(0., 0., -1.)
+        );
+
+        let aabb = Aabb3::new(Vec3::splat(-1.), Vec3::splat(1.));
+        let hit = ray.intersect_aabb(&aabb).unwrap();
+        assert_eq!(hit.distance, 4.);
+        assert_eq!(hit.normal, v(0., 0., 1.));
+        let inside = Ray::new(Vec3::splat(0.), v(1., 0., 0.));
+        let hit = inside.intersect_aabb(&aabb).unwrap();
+        assert_eq!((hit.distance, hit.normal), (1., v(1., 0., 0.)));
+        assert_eq!(
+            Ray::new(v(2., 0., 5.), v(0., 0., -1.)).intersect_aabb(&aabb),
+            None
+        );
+        assert_eq!(
+            Ray::new(v(0., 0., 5.), v(0., 0., 1.)).intersect_aabb(&aabb),
+            None
+        );
+        // Along a face, borders are inside
+        let hit = Ray::new(v(1., 0., 5.), v(0., 0., -1.))
+            .intersect_aabb(&aabb)
+            .unwrap();
+        assert_eq!((hit.distance, hit.normal), (4., v(0., 0., 1.)));
+
+        assert_eq!(
+            aabb.intersect_ray(Vec3::splat(0.), v(1., 0., 0.)),
+            Some((-1., 1.))
+        );
+        let square = Aabb2::new(Vec2::splat(0.), Vec2::splat(1.));
+        assert_eq!(
+            square.intersect_ray(
+                Vec2::from_components(-1., 1.),
+                Vec2::from_components(1., 0.)
+            ),
+            Some((1., 2.))
+        );
+    }
+
+    #[test]
+    fn contacts() {
+        let aabb = Aabb3::new(Vec3::splat(0.), Vec3::splat(2.));
+
+        let sphere = Sphere::new(v(3., 1., 1.), 1.5);
+        assert!(sphere.intersects_aabb(&aabb));
+        assert_eq!(
+            sphere.contact_aabb(&aabb),
+            Some(Contact {
+                point: v(2., 1., 1.),
+                normal: v(-1., 0., 0.),
+                depth: 0.5
+            })
+        );
+        let inside = Sphere::new(v(1.5, 1., 1.), 1.);
+        let contact = inside.contact_aabb(&aabb).unwrap();
+        assert_eq!((contact.normal, contact.depth), (v(-1., 0., 0.), 1.5));
+        assert!(!Sphere::new(v(3., 3., 3.), 1.).intersects_aabb(&aabb));
+
+        let other = Aabb3::new(v(1.5, 0.5, -1.), v(4., 1.5, 3.));
+        assert!(aabb.intersects(&other));
+        let contact = aabb.contact(&other).unwrap();
+        assert_eq!((contact.normal, contact.depth), (v(1., 0., 0.), 0.5));
+        assert_eq!(contact.point, v(1.75, 1., 1.));
+        assert_eq!(
+            aabb.contact(&Aabb3::new(Vec3::splat(3.), Vec3::splat(4.))),
+            None
+        );
+    }
+
+    #[test]
+    fn closest_points() {
+        let triangle = Triangle::new(v(0., 0., 0.), v(2., 0., 0.), v(0., 2., 0.));
+        assert_eq!(triangle.closest_point(v(0.5, 0.5, 3.)), v(0.5, 0.5, 0.));
+        assert_eq!(triangle.closest_point(v(-1., -1., 0.)), triangle.a);
+        assert_eq!(triangle.closest_point(v(1., -1., 1.)), v(1., 0., 0.));
+        assert_eq!(triangle.closest_point(v(2., 2., 0.)), v(1., 1., 0.));
+        assert_eq!(triangle.distance_to_point(v(0.5, 0.5, -3.)), 3.);
+
+        let segment = Segment::new(v(0., 0., 0.), v(0., 0., 4.));
+        assert_eq!(segment.closest_point(v(1., 0., 1.)), v(0., 0., 1.));
+        assert_eq!(segment.closest_point(v(0., 0., 9.)), segment.b);
+
+        let aabb = Aabb3::new(Vec3::splat(0.), Vec3::splat(1.));
+        assert_eq!(aabb.closest_point(v(0.5, 2., -1.)), v(0.5, 1., 0.));
+        assert_eq!(aabb.distance_to_point(v(0.5, 0.5, 3.)), 2.);
+
+        let sphere = Sphere::new(Vec3::splat(0.), 1.);
+        assert_eq!(sphere.closest_point(v(0., 3., 0.)), v(0., 1., 0.));
+        assert_eq!(sphere.distance_to_point(v(0., 0.5, 0.)), 0.);
+
+        let ray = Ray::new(Vec3::splat(0.), v(1., 0., 0.));
+        assert_eq!(ray.closest_point(v(-2., 1., 0.)), Vec3::splat(0.));
+        assert_eq!(ray.distance_to_point(v(3., 4., 0.)), 4.);
+    }
+}
@@ -1,12 +1,19 @@
-#![feature(portable_simd)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
 pub mod dim2;
+pub mod dim3;
 pub mod dim4;
+pub mod frustum;
+pub mod geometry;
 pub mod graphic;
+pub mod intersection;
 pub mod rand;
+pub mod simd;
 
 mod mat_trait;
+mod scalar;
 mod vec_trait;
 
 pub use mat_trait::Matrix;
+pub use scalar::Scalar;
 pub use vec_trait::Vector;
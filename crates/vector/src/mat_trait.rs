@@ -1,16 +1,25 @@
-use crate::{dim2::Mat2, dim4::Mat4, Vector};
+use crate::{
+    dim2::{DMat2, Mat2},
+    dim3::{DMat3, Mat3},
+    dim4::{DMat4, Mat4},
+    Scalar, Vector,
+};
 
 pub trait Matrix<const DIM: usize>: Sized + Copy {
-    type VecDIM: Vector<DIM>;
+    type Scalar: Scalar;
+    type VecDIM: Vector<DIM, Scalar = Self::Scalar>;
     fn add(self, other: Self) -> Self;
     fn sub(self, other: Self) -> Self;
     fn neg(self) -> Self;
 
     fn transpose(self) -> Self;
-    fn from_rows(rows: &[[f32; DIM]; DIM]) -> Self;
+    fn from_rows(rows: &[[Self::Scalar; DIM]; DIM]) -> Self;
     fn identity() -> Self;
-    fn into_rows(self) -> [[f32; DIM]; DIM];
+    fn into_rows(self) -> [[Self::Scalar; DIM]; DIM];
     fn vec_mul(vec: Self::VecDIM, this: Self) -> Self::VecDIM;
+    fn determinant(self) -> Self::Scalar;
+    /// Returns `None` when the matrix is singular
+    fn try_inverse(self) -> Option<Self>;
     fn mul_vec(this: Self, vec: Self::VecDIM) -> Self::VecDIM {
         Self::vec_mul(vec, this.transpose())
     }
@@ -25,7 +34,7 @@ pub trait Matrix<const DIM: usize>: Sized + Copy {
 }
 
 macro_rules! MatrixImpls {
-    ($dim: expr, $m: ident) => {
+    ($dim: expr, $m: ident, $s: ty) => {
         impl std::ops::Add for $m {
             type Output = Self;
 
@@ -51,7 +60,7 @@ macro_rules! MatrixImpls {
         }
 
         impl std::ops::Index<(usize, usize)> for $m {
-            type Output = f32;
+            type Output = $s;
 
             fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
                 &self.0[row].as_array()[col]
@@ -90,5 +99,86 @@ macro_rules! MatrixImpls {
     };
 }
 
-MatrixImpls!(2, Mat2);
-MatrixImpls!(4, Mat4);
+MatrixImpls!(2, Mat2, f32);
+MatrixImpls!(3, Mat3, f32);
+MatrixImpls!(4, Mat4, f32);
+MatrixImpls!(2, DMat2, f64);
+MatrixImpls!(3, DMat3, f64);
+MatrixImpls!(4, DMat4, f64);
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dim2::Mat2,
+        dim3::Mat3,
+        dim4::{DMat4, Mat4},
+        Matrix,
+    };
+
+    fn assert_near<const DIM: usize, M>(a: M, b: M)
+    where
+        M: Matrix<DIM>,
+        M::Scalar: Into<f64>,
+    {
+        let (a, b) = (a.into_rows(), b.into_rows());
+        for (ra, rb) in a.iter().zip(b.iter()) {
+            for (x, y) in ra.iter().zip(rb.iter()) {
+                let (x, y): (f64, f64) = ((*x).into(), (*y).into());
+                assert!((x - y).abs() < 1e-5, "{a:?} != {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn determinant() {
+        assert_eq!(Mat2::from_array(&[1., 2., 3., 4.]).determinant(), -2.);
+        assert_eq!(
+            Mat3::from_array(&[2., 0., 0., 0., 3., 0., 1., 1., 4.]).determinant(),
+            24.
+        );
+        let m = Mat4::from_rows(&[
+            [1., 0., 2., -1.],
+            [3., 0., 0., 5.],
+            [2., 1., 4., -3.],
+            [1., 0., 5., 0.],
+        ]);
+        assert_eq!(m.determinant(), 30.);
+        assert_eq!(Mat4::identity().determinant(), 1.);
+    }
+
+    #[test]
+    fn inverse() {
+        let m = Mat2::from_array(&[1., 2., 3., 4.]);
+        assert_near(m * m.try_inverse().unwrap(), Mat2::identity());
+
+        let m = Mat3::from_array(&[2., 1., 0., 0., 3., 1., 1., 1., 4.]);
+        assert_near(m * m.try_inverse().unwrap(), Mat3::identity());
+
+        let m = Mat4::from_rows(&[
+            [1., 0., 2., -1.],
+            [3., 0., 0., 5.],
+            [2., 1., 4., -3.],
+            [1., 0., 5., 0.],
+        ]);
+        assert_near(m * m.try_inverse().unwrap(), Mat4::identity());
+        assert_near(m.try_inverse().unwrap() * m, Mat4::identity());
+
+        let m = DMat4::from(m);
+        assert_near(m * m.try_inverse().unwrap(), DMat4::identity());
+
+        assert!(Mat2::from_array(&[1., 2., 2., 4.]).try_inverse().is_none());
+        assert!(Mat4::from_rows(&[[0.; 4]; 4]).try_inverse().is_none());
+    }
+
+    #[test]
+    fn affine_inverse() {
+        let m = Mat4::from_rows(&[
+            [0., -2., 0., 1.],
+            [2., 0., 0., 2.],
+            [0., 0., 3., 3.],
+            [0., 0., 0., 1.],
+        ]);
+        assert_near(m.affine_inverse().unwrap(), m.try_inverse().unwrap());
+        assert_near(m * m.affine_inverse().unwrap(), Mat4::identity());
+    }
+}
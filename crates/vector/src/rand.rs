@@ -1,6 +1,6 @@
 use rand::prelude::Distribution;
 
-use crate::{dim2::Vec2, dim4::Vec4, Vector};
+use crate::{dim2::Vec2, dim3::Vec3, dim4::Vec4, Vector};
 
 pub struct Vec2Uniform {
     inner_x: rand::distributions::Uniform<f32>,
@@ -45,6 +45,56 @@ impl rand::distributions::uniform::SampleUniform for Vec2 {
     type Sampler = Vec2Uniform;
 }
 
+pub struct Vec3Uniform {
+    inner_x: rand::distributions::Uniform<f32>,
+    inner_y: rand::distributions::Uniform<f32>,
+    inner_z: rand::distributions::Uniform<f32>,
+}
+
+impl rand::distributions::uniform::UniformSampler for Vec3Uniform {
+    type X = Vec3;
+
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Vec3 {
+        Vec3::from_components(
+            self.inner_x.sample(rng),
+            self.inner_y.sample(rng),
+            self.inner_z.sample(rng),
+        )
+    }
+
+    fn new<B1, B2>(low: B1, high: B2) -> Self
+    where
+        B1: rand::distributions::uniform::SampleBorrow<Self::X> + Sized,
+        B2: rand::distributions::uniform::SampleBorrow<Self::X> + Sized,
+    {
+        let [low_x, low_y, low_z] = low.borrow().into_array();
+        let [high_x, high_y, high_z] = high.borrow().into_array();
+        Self {
+            inner_x: rand::distributions::Uniform::new(low_x, high_x),
+            inner_y: rand::distributions::Uniform::new(low_y, high_y),
+            inner_z: rand::distributions::Uniform::new(low_z, high_z),
+        }
+    }
+
+    fn new_inclusive<B1, B2>(low: B1, high: B2) -> Self
+    where
+        B1: rand::distributions::uniform::SampleBorrow<Self::X> + Sized,
+        B2: rand::distributions::uniform::SampleBorrow<Self::X> + Sized,
+    {
+        let [low_x, low_y, low_z] = low.borrow().into_array();
+        let [high_x, high_y, high_z] = high.borrow().into_array();
+        Self {
+            inner_x: rand::distributions::Uniform::new_inclusive(low_x, high_x),
+            inner_y: rand::distributions::Uniform::new_inclusive(low_y, high_y),
+            inner_z: rand::distributions::Uniform::new_inclusive(low_z, high_z),
+        }
+    }
+}
+
+impl rand::distributions::uniform::SampleUniform for Vec3 {
+    type Sampler = Vec3Uniform;
+}
+
 pub struct Vec4Uniform {
     inner_x: rand::distributions::Uniform<f32>,
     inner_y: rand::distributions::Uniform<f32>,
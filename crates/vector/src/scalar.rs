@@ -0,0 +1,56 @@
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Floating point type of the components of vectors and matrices
+pub trait Scalar:
+    Copy
+    + Debug
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + std::iter::Sum
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn sqrt(self) -> Self;
+    fn is_finite(self) -> bool;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn abs(self) -> Self;
+}
+
+macro_rules! ScalarImpls {
+    ($s: ty) => {
+        impl Scalar for $s {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+
+            fn sqrt(self) -> Self {
+                <$s>::sqrt(self)
+            }
+
+            fn is_finite(self) -> bool {
+                <$s>::is_finite(self)
+            }
+
+            fn min(self, other: Self) -> Self {
+                <$s>::min(self, other)
+            }
+
+            fn max(self, other: Self) -> Self {
+                <$s>::max(self, other)
+            }
+
+            fn abs(self) -> Self {
+                <$s>::abs(self)
+            }
+        }
+    };
+}
+
+ScalarImpls!(f32);
+ScalarImpls!(f64);
@@ -0,0 +1,364 @@
+//! Storage used by the vectors and matrices.
+//!
+//! With the `simd` feature (nightly only) this is `std::simd`, otherwise a scalar stand-in made of
+//! plain arrays exposing the subset of the `std::simd` API used by this crate.
+
+#[cfg(feature = "simd")]
+pub use std::simd::{
+    cmp::{SimdPartialEq, SimdPartialOrd},
+    f32x2, f32x4, f64x2, f64x4, i32x2, i32x4, mask32x2, mask32x4, u32x4, Mask, Select, Simd,
+    SimdElement,
+};
+
+#[cfg(feature = "simd")]
+pub(crate) use std::simd::simd_swizzle;
+
+#[cfg(not(feature = "simd"))]
+pub use scalar::{
+    f32x2, f32x4, f64x2, f64x4, i32x2, i32x4, mask32x2, mask32x4, u32x4, Mask, Select, Simd,
+    SimdElement, SimdPartialEq, SimdPartialOrd,
+};
+
+#[cfg(not(feature = "simd"))]
+pub(crate) use scalar::simd_swizzle;
+
+#[cfg(not(feature = "simd"))]
+pub(crate) mod scalar {
+    use std::marker::PhantomData;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct Simd<T, const N: usize>(pub [T; N]);
+
+    /// Lane-wise booleans, `T` only mirrors the `std::simd` element type
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Mask<T, const N: usize>([bool; N], PhantomData<T>);
+
+    #[allow(non_camel_case_types)]
+    pub type f32x2 = Simd<f32, 2>;
+    #[allow(non_camel_case_types)]
+    pub type f32x4 = Simd<f32, 4>;
+    #[allow(non_camel_case_types)]
+    pub type f64x2 = Simd<f64, 2>;
+    #[allow(non_camel_case_types)]
+    pub type f64x4 = Simd<f64, 4>;
+    #[allow(non_camel_case_types)]
+    pub type i32x2 = Simd<i32, 2>;
+    #[allow(non_camel_case_types)]
+    pub type i32x4 = Simd<i32, 4>;
+    #[allow(non_camel_case_types)]
+    pub type u32x4 = Simd<u32, 4>;
+    #[allow(non_camel_case_types)]
+    pub type mask32x2 = Mask<i32, 2>;
+    #[allow(non_camel_case_types)]
+    pub type mask32x4 = Mask<i32, 4>;
+
+    /// Types that can be stored in [`Simd`]
+    pub trait SimdElement: Copy {}
+
+    impl<T: Copy> SimdElement for T {}
+
+    impl<T: SimdElement, const N: usize> Simd<T, N> {
+        #[inline]
+        pub fn splat(value: T) -> Self {
+            Self([value; N])
+        }
+
+        #[inline]
+        pub const fn from_array(array: [T; N]) -> Self {
+            Self(array)
+        }
+
+        #[inline]
+        pub const fn to_array(self) -> [T; N] {
+            self.0
+        }
+
+        #[inline]
+        pub const fn as_array(&self) -> &[T; N] {
+            &self.0
+        }
+
+        #[inline]
+        pub fn as_mut_array(&mut self) -> &mut [T; N] {
+            &mut self.0
+        }
+    }
+
+    impl<T, const N: usize> std::ops::Index<usize> for Simd<T, N> {
+        type Output = T;
+
+        fn index(&self, index: usize) -> &Self::Output {
+            &self.0[index]
+        }
+    }
+
+    impl<T, const N: usize> std::ops::IndexMut<usize> for Simd<T, N> {
+        fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+            &mut self.0[index]
+        }
+    }
+
+    /// Arithmetic of a single lane, integers wrap around on overflow like in `std::simd`
+    pub trait LaneArith: Copy {
+        fn lane_add(self, rhs: Self) -> Self;
+        fn lane_sub(self, rhs: Self) -> Self;
+        fn lane_mul(self, rhs: Self) -> Self;
+        fn lane_div(self, rhs: Self) -> Self;
+    }
+
+    /// Negation of a single lane, integers wrap around on overflow like in `std::simd`
+    pub trait LaneNeg: Copy {
+        fn lane_neg(self) -> Self;
+    }
+
+    macro_rules! FloatLane {
+        ($s: ty) => {
+            impl LaneArith for $s {
+                #[inline]
+                fn lane_add(self, rhs: Self) -> Self {
+                    self + rhs
+                }
+
+                #[inline]
+                fn lane_sub(self, rhs: Self) -> Self {
+                    self - rhs
+                }
+
+                #[inline]
+                fn lane_mul(self, rhs: Self) -> Self {
+                    self * rhs
+                }
+
+                #[inline]
+                fn lane_div(self, rhs: Self) -> Self {
+                    self / rhs
+                }
+            }
+
+            impl LaneNeg for $s {
+                #[inline]
+                fn lane_neg(self) -> Self {
+                    -self
+                }
+            }
+        };
+    }
+
+    // Division by zero still panics, as in `std::simd`
+    macro_rules! IntLane {
+        ($s: ty) => {
+            impl LaneArith for $s {
+                #[inline]
+                fn lane_add(self, rhs: Self) -> Self {
+                    self.wrapping_add(rhs)
+                }
+
+                #[inline]
+                fn lane_sub(self, rhs: Self) -> Self {
+                    self.wrapping_sub(rhs)
+                }
+
+                #[inline]
+                fn lane_mul(self, rhs: Self) -> Self {
+                    self.wrapping_mul(rhs)
+                }
+
+                #[inline]
+                fn lane_div(self, rhs: Self) -> Self {
+                    self / rhs
+                }
+            }
+        };
+        (signed $s: ty) => {
+            IntLane!($s);
+
+            impl LaneNeg for $s {
+                #[inline]
+                fn lane_neg(self) -> Self {
+                    self.wrapping_neg()
+                }
+            }
+        };
+    }
+
+    FloatLane!(f32);
+    FloatLane!(f64);
+    IntLane!(signed i32);
+    IntLane!(u32);
+
+    impl<T: LaneNeg, const N: usize> std::ops::Neg for Simd<T, N> {
+        type Output = Self;
+
+        fn neg(self) -> Self::Output {
+            Self(self.0.map(LaneNeg::lane_neg))
+        }
+    }
+
+    macro_rules! LaneWiseOp {
+        ($op: ident, $f: ident, $lane_f: ident) => {
+            impl<T: LaneArith, const N: usize> std::ops::$op for Simd<T, N> {
+                type Output = Self;
+
+                #[inline]
+                fn $f(self, rhs: Self) -> Self::Output {
+                    Self(std::array::from_fn(|i| self.0[i].$lane_f(rhs.0[i])))
+                }
+            }
+        };
+    }
+
+    LaneWiseOp!(Add, add, lane_add);
+    LaneWiseOp!(Sub, sub, lane_sub);
+    LaneWiseOp!(Mul, mul, lane_mul);
+    LaneWiseOp!(Div, div, lane_div);
+
+    impl<T: LaneArith + Default, const N: usize> std::iter::Sum for Simd<T, N> {
+        fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+            iter.fold(Self::splat(T::default()), |a, b| a + b)
+        }
+    }
+
+    impl<T, const N: usize> Mask<T, N> {
+        #[inline]
+        pub fn splat(value: bool) -> Self {
+            Self([value; N], PhantomData)
+        }
+
+        #[inline]
+        pub fn from_array(array: [bool; N]) -> Self {
+            Self(array, PhantomData)
+        }
+
+        #[inline]
+        pub fn to_array(self) -> [bool; N] {
+            self.0
+        }
+
+        #[inline]
+        pub fn test(&self, index: usize) -> bool {
+            self.0[index]
+        }
+
+        #[inline]
+        pub fn set(&mut self, index: usize, value: bool) {
+            self.0[index] = value
+        }
+
+        #[inline]
+        pub fn any(self) -> bool {
+            self.0.into_iter().any(|b| b)
+        }
+
+        #[inline]
+        pub fn all(self) -> bool {
+            self.0.into_iter().all(|b| b)
+        }
+    }
+
+    pub trait Select<T> {
+        fn select(self, true_values: T, false_values: T) -> T;
+    }
+
+    impl<T, U: SimdElement, const N: usize> Select<Simd<U, N>> for Mask<T, N> {
+        #[inline]
+        fn select(self, true_values: Simd<U, N>, false_values: Simd<U, N>) -> Simd<U, N> {
+            Simd(std::array::from_fn(|i| {
+                if self.0[i] {
+                    true_values.0[i]
+                } else {
+                    false_values.0[i]
+                }
+            }))
+        }
+    }
+
+    impl<T, const N: usize> std::ops::Not for Mask<T, N> {
+        type Output = Self;
+
+        fn not(self) -> Self::Output {
+            Self(self.0.map(|b| !b), PhantomData)
+        }
+    }
+
+    macro_rules! MaskOp {
+        ($op: ident, $f: ident) => {
+            impl<T, const N: usize> std::ops::$op for Mask<T, N> {
+                type Output = Self;
+
+                #[inline]
+                fn $f(self, rhs: Self) -> Self::Output {
+                    Self(
+                        std::array::from_fn(|i| std::ops::$op::$f(self.0[i], rhs.0[i])),
+                        PhantomData,
+                    )
+                }
+            }
+        };
+    }
+
+    MaskOp!(BitAnd, bitand);
+    MaskOp!(BitOr, bitor);
+    MaskOp!(BitXor, bitxor);
+
+    pub trait SimdPartialEq {
+        type Mask;
+
+        fn simd_eq(self, other: Self) -> Self::Mask;
+        fn simd_ne(self, other: Self) -> Self::Mask;
+    }
+
+    pub trait SimdPartialOrd: SimdPartialEq {
+        fn simd_lt(self, other: Self) -> Self::Mask;
+        fn simd_le(self, other: Self) -> Self::Mask;
+        fn simd_gt(self, other: Self) -> Self::Mask;
+        fn simd_ge(self, other: Self) -> Self::Mask;
+    }
+
+    macro_rules! LaneWiseCmp {
+        ($f: ident, $op: tt) => {
+            #[inline]
+            fn $f(self, other: Self) -> Self::Mask {
+                Mask::from_array(std::array::from_fn(|i| self.0[i] $op other.0[i]))
+            }
+        };
+    }
+
+    impl<T: SimdElement + PartialOrd, const N: usize> SimdPartialEq for Simd<T, N> {
+        type Mask = Mask<i32, N>;
+
+        LaneWiseCmp!(simd_eq, ==);
+        LaneWiseCmp!(simd_ne, !=);
+    }
+
+    impl<T: SimdElement + PartialOrd, const N: usize> SimdPartialOrd for Simd<T, N> {
+        LaneWiseCmp!(simd_lt, <);
+        LaneWiseCmp!(simd_le, <=);
+        LaneWiseCmp!(simd_gt, >);
+        LaneWiseCmp!(simd_ge, >=);
+    }
+
+    /// Lane `index` of the concatenation of `a` and `b`
+    #[doc(hidden)]
+    #[inline]
+    pub fn lane<T: Copy, const N: usize>(a: &Simd<T, N>, b: &Simd<T, N>, index: usize) -> T {
+        if index < N {
+            a.0[index]
+        } else {
+            b.0[index - N]
+        }
+    }
+
+    macro_rules! simd_swizzle {
+        ($a: expr, [$($index: expr),+ $(,)?]) => {{
+            let a = $a;
+            $crate::simd::Simd::from_array([$(a.0[$index]),+])
+        }};
+        ($a: expr, $b: expr, [$($index: expr),+ $(,)?]) => {{
+            let (a, b) = ($a, $b);
+            $crate::simd::Simd::from_array([$($crate::simd::scalar::lane(&a, &b, $index)),+])
+        }};
+    }
+
+    pub(crate) use simd_swizzle;
+}
@@ -1,24 +1,75 @@
-use crate::{dim2::Vec2, dim4::Vec4};
+use crate::{
+    dim2::{BVec2, DVec2, IVec2, Vec2},
+    dim3::{DVec3, Vec3},
+    dim4::{BVec4, DVec4, IVec4, UVec4, Vec4},
+    simd::{Select, SimdPartialEq, SimdPartialOrd},
+    Scalar,
+};
 
 pub trait Vector<const DIM: usize>: Sized + Copy {
-    fn from_array(arr: [f32; DIM]) -> Self;
-    fn into_array(self) -> [f32; DIM];
+    type Scalar: Scalar;
+
+    fn from_array(arr: [Self::Scalar; DIM]) -> Self;
+    fn into_array(self) -> [Self::Scalar; DIM];
 
     fn add(self, other: Self) -> Self;
     fn sub(self, other: Self) -> Self;
     fn neg(self) -> Self;
     fn component_mul(self, other: Self) -> Self;
-    fn splat(f: f32) -> Self;
-    fn length_squared(self) -> f32 {
-        self.component_mul(self).into_array().into_iter().sum()
+    fn splat(f: Self::Scalar) -> Self;
+    fn scale(self, f: Self::Scalar) -> Self {
+        self.component_mul(Self::splat(f))
+    }
+    fn dot(self, other: Self) -> Self::Scalar {
+        self.component_mul(other).into_array().into_iter().sum()
+    }
+    fn length_squared(self) -> Self::Scalar {
+        self.dot(self)
     }
-    fn length(self) -> f32 {
+    fn length(self) -> Self::Scalar {
         self.length_squared().sqrt()
     }
+    fn distance_squared(self, other: Self) -> Self::Scalar {
+        other.sub(self).length_squared()
+    }
+    fn distance(self, other: Self) -> Self::Scalar {
+        other.sub(self).length()
+    }
+    /// The result is not finite if `self` has a zero length, see [`Vector::try_normalize`]
+    fn normalize(self) -> Self {
+        self.scale(Self::Scalar::ONE / self.length())
+    }
+    fn try_normalize(self) -> Option<Self> {
+        let inv_length = Self::Scalar::ONE / self.length();
+        (inv_length.is_finite() && inv_length > Self::Scalar::ZERO).then(|| self.scale(inv_length))
+    }
+    fn lerp(self, other: Self, t: Self::Scalar) -> Self {
+        self.add(other.sub(self).scale(t))
+    }
+    /// `normal` is expected to be normalized
+    fn reflect(self, normal: Self) -> Self {
+        let d = self.dot(normal);
+        self.sub(normal.scale(d + d))
+    }
+    /// Projection of `self` onto the line directed by `other`
+    fn project_onto(self, other: Self) -> Self {
+        other.scale(self.dot(other) / other.length_squared())
+    }
+    fn min(self, other: Self) -> Self {
+        let (a, b) = (self.into_array(), other.into_array());
+        Self::from_array(std::array::from_fn(|i| a[i].min(b[i])))
+    }
+    fn max(self, other: Self) -> Self {
+        let (a, b) = (self.into_array(), other.into_array());
+        Self::from_array(std::array::from_fn(|i| a[i].max(b[i])))
+    }
+    fn abs(self) -> Self {
+        Self::from_array(self.into_array().map(Scalar::abs))
+    }
 }
 
 macro_rules! VectorImpls {
-    ($dim: expr, $v: ident) => {
+    ($dim: expr, $v: ident, $s: ty) => {
         impl std::ops::Add for $v {
             type Output = Self;
 
@@ -43,15 +94,15 @@ macro_rules! VectorImpls {
             }
         }
 
-        impl std::ops::Mul<f32> for $v {
+        impl std::ops::Mul<$s> for $v {
             type Output = Self;
 
-            fn mul(self, other: f32) -> Self::Output {
+            fn mul(self, other: $s) -> Self::Output {
                 Vector::component_mul(self, Vector::splat(other))
             }
         }
 
-        impl std::ops::Mul<$v> for f32 {
+        impl std::ops::Mul<$v> for $s {
             type Output = $v;
 
             fn mul(self, other: $v) -> Self::Output {
@@ -59,8 +110,74 @@ macro_rules! VectorImpls {
             }
         }
 
+        // 3D vectors are padded to 4 lanes, the padding lane must stay out of reach
         impl std::ops::Index<usize> for $v {
-            type Output = f32;
+            type Output = $s;
+
+            fn index(&self, index: usize) -> &Self::Output {
+                &self.0.as_array()[..$dim][index]
+            }
+        }
+
+        impl std::ops::IndexMut<usize> for $v {
+            fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+                &mut self.0.as_mut_array()[..$dim][index]
+            }
+        }
+    };
+}
+
+VectorImpls!(2, Vec2, f32);
+VectorImpls!(3, Vec3, f32);
+VectorImpls!(4, Vec4, f32);
+VectorImpls!(2, DVec2, f64);
+VectorImpls!(3, DVec3, f64);
+VectorImpls!(4, DVec4, f64);
+
+macro_rules! IntVectorImpls {
+    ($v: ident, $s: ty) => {
+        impl std::ops::Add for $v {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl std::ops::Sub for $v {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl std::ops::Mul for $v {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self::Output {
+                Self(self.0 * rhs.0)
+            }
+        }
+
+        impl std::ops::Mul<$s> for $v {
+            type Output = Self;
+
+            fn mul(self, other: $s) -> Self::Output {
+                self * Self::splat(other)
+            }
+        }
+
+        impl std::ops::Mul<$v> for $s {
+            type Output = $v;
+
+            fn mul(self, other: $v) -> Self::Output {
+                $v::splat(self) * other
+            }
+        }
+
+        impl std::ops::Index<usize> for $v {
+            type Output = $s;
 
             fn index(&self, index: usize) -> &Self::Output {
                 &self.0.as_array()[index]
@@ -73,7 +190,215 @@ macro_rules! VectorImpls {
             }
         }
     };
+    (signed $v: ident, $s: ty) => {
+        IntVectorImpls!($v, $s);
+
+        impl std::ops::Neg for $v {
+            type Output = Self;
+
+            fn neg(self) -> Self::Output {
+                Self(-self.0)
+            }
+        }
+    };
 }
 
-VectorImpls!(2, Vec2);
-VectorImpls!(4, Vec4);
+macro_rules! MaskImpls {
+    ($b: ident) => {
+        impl $b {
+            pub fn any(self) -> bool {
+                self.0.any()
+            }
+
+            pub fn all(self) -> bool {
+                self.0.all()
+            }
+
+            pub fn test(self, index: usize) -> bool {
+                self.0.test(index)
+            }
+        }
+
+        impl std::ops::BitAnd for $b {
+            type Output = Self;
+
+            fn bitand(self, rhs: Self) -> Self::Output {
+                Self(self.0 & rhs.0)
+            }
+        }
+
+        impl std::ops::BitOr for $b {
+            type Output = Self;
+
+            fn bitor(self, rhs: Self) -> Self::Output {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl std::ops::BitXor for $b {
+            type Output = Self;
+
+            fn bitxor(self, rhs: Self) -> Self::Output {
+                Self(self.0 ^ rhs.0)
+            }
+        }
+
+        impl std::ops::Not for $b {
+            type Output = Self;
+
+            fn not(self) -> Self::Output {
+                Self(!self.0)
+            }
+        }
+    };
+}
+
+// Component-wise comparisons, prefixed to stay clear of the `PartialEq` and `PartialOrd` methods
+macro_rules! ComparisonImpls {
+    ($v: ident, $b: ident) => {
+        impl $v {
+            pub fn cmplt(self, other: Self) -> $b {
+                $b(self.0.simd_lt(other.0))
+            }
+
+            pub fn cmple(self, other: Self) -> $b {
+                $b(self.0.simd_le(other.0))
+            }
+
+            pub fn cmpgt(self, other: Self) -> $b {
+                $b(self.0.simd_gt(other.0))
+            }
+
+            pub fn cmpge(self, other: Self) -> $b {
+                $b(self.0.simd_ge(other.0))
+            }
+
+            pub fn cmpeq(self, other: Self) -> $b {
+                $b(self.0.simd_eq(other.0))
+            }
+
+            pub fn cmpne(self, other: Self) -> $b {
+                $b(self.0.simd_ne(other.0))
+            }
+
+            /// Takes the components of `a` where `mask` is set and the ones of `b` elsewhere
+            pub fn select(mask: $b, a: Self, b: Self) -> Self {
+                Self(mask.0.select(a.0, b.0))
+            }
+        }
+    };
+}
+
+IntVectorImpls!(signed IVec2, i32);
+IntVectorImpls!(signed IVec4, i32);
+IntVectorImpls!(UVec4, u32);
+
+MaskImpls!(BVec2);
+MaskImpls!(BVec4);
+
+ComparisonImpls!(Vec2, BVec2);
+ComparisonImpls!(Vec4, BVec4);
+ComparisonImpls!(IVec2, BVec2);
+ComparisonImpls!(IVec4, BVec4);
+ComparisonImpls!(UVec4, BVec4);
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dim2::{BVec2, IVec2, Vec2},
+        dim3::Vec3,
+        dim4::{BVec4, IVec4, UVec4, Vec4},
+        Vector,
+    };
+
+    #[test]
+    fn products() {
+        let x = Vec3::from_components(1., 0., 0.);
+        let y = Vec3::from_components(0., 1., 0.);
+        assert_eq!(x.cross(y), Vec3::from_components(0., 0., 1.));
+        assert_eq!(y.cross(x), Vec3::from_components(0., 0., -1.));
+        assert_eq!(x.dot(y), 0.);
+
+        let a = Vec2::from_components(1., 0.);
+        let b = Vec2::from_components(0., 1.);
+        assert_eq!(a.perp_dot(b), 1.);
+        assert_eq!(a.perp(), b);
+    }
+
+    #[test]
+    fn normalize() {
+        let v = Vec3::from_components(3., 0., 4.);
+        assert_eq!(v.length(), 5.);
+        assert_eq!(v.normalize(), Vec3::from_components(0.6, 0., 0.8));
+        assert_eq!(Vec3::splat(0.).try_normalize(), None);
+    }
+
+    #[test]
+    fn projections() {
+        let v = Vec2::from_components(1., -1.);
+        let n = Vec2::from_components(0., 1.);
+        assert_eq!(v.reflect(n), Vec2::from_components(1., 1.));
+        assert_eq!(v.project_onto(n), Vec2::from_components(0., -1.));
+        assert_eq!(v.lerp(n, 0.5), Vec2::from_components(0.5, 0.));
+        assert_eq!(v.distance(n), 5f32.sqrt());
+    }
+
+    #[test]
+    fn masks() {
+        let a = Vec4::from_components(1., 2., 3., 4.);
+        let b = Vec4::splat(2.5);
+        let lt = a.cmplt(b);
+        assert_eq!(lt, BVec4::from_components(true, true, false, false));
+        assert_eq!(a.cmpge(b), !lt);
+        assert!(lt.any() && !lt.all());
+        assert!((lt | a.cmpge(b)).all());
+        assert_eq!(
+            Vec4::select(lt, a, b),
+            Vec4::from_components(1., 2., 2.5, 2.5)
+        );
+
+        let v = Vec2::from_components(-1.5, 3.7);
+        assert_eq!(v.as_ivec2(), IVec2::from_components(-1, 3));
+        assert_eq!(
+            v.as_ivec2().cmpeq(IVec2::splat(3)),
+            BVec2::from_components(false, true)
+        );
+        assert_eq!(a.as_uvec4(), UVec4::from_components(1, 2, 3, 4));
+        assert_eq!(-a.as_ivec4() * 2, IVec4::from_components(-2, -4, -6, -8));
+        assert_eq!(a.as_ivec4().as_vec4(), a);
+
+        // The `PartialEq` methods are still reachable
+        assert!(a.eq(&a) && a.ne(&b));
+        assert!(v.as_ivec2().eq(&IVec2::from_components(-1, 3)));
+    }
+
+    // Same results with and without the `simd` feature
+    #[test]
+    fn integer_overflow_wraps() {
+        let max = IVec2::from_components(i32::MAX, 0);
+        let min = IVec2::from_components(i32::MIN, 0);
+        assert_eq!(max + IVec2::from_components(1, 0), min);
+        assert_eq!(min - IVec2::from_components(1, 0), max);
+        assert_eq!(max * 2, IVec2::from_components(-2, 0));
+        assert_eq!(-min, min);
+        assert_eq!(
+            UVec4::splat(0) - UVec4::from_components(1, 0, 0, 0),
+            UVec4::from_components(u32::MAX, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn indexing() {
+        let mut v = Vec3::from_components(1., 2., 3.);
+        v[2] = 4.;
+        assert_eq!(v, Vec3::from_components(1., 2., 4.));
+        assert_eq!(v[1], 2.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn padding_lane_is_out_of_bounds() {
+        let mut v = Vec3::from_components(1., 2., 3.);
+        v[3] = 5.;
+    }
+}